@@ -0,0 +1,41 @@
+PROVIDE(POWER_CLOCK = DefaultHandler);
+PROVIDE(RADIO = DefaultHandler);
+PROVIDE(UARTE0_UART0 = DefaultHandler);
+PROVIDE(SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0 = DefaultHandler);
+PROVIDE(SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1 = DefaultHandler);
+PROVIDE(NFCT = DefaultHandler);
+PROVIDE(GPIOTE = DefaultHandler);
+PROVIDE(SAADC = DefaultHandler);
+PROVIDE(TIMER0 = DefaultHandler);
+PROVIDE(TIMER1 = DefaultHandler);
+PROVIDE(TIMER2 = DefaultHandler);
+PROVIDE(RTC0 = DefaultHandler);
+PROVIDE(TEMP = DefaultHandler);
+PROVIDE(RNG = DefaultHandler);
+PROVIDE(ECB = DefaultHandler);
+PROVIDE(CCM_AAR = DefaultHandler);
+PROVIDE(WDT = DefaultHandler);
+PROVIDE(RTC1 = DefaultHandler);
+PROVIDE(QDEC = DefaultHandler);
+PROVIDE(COMP_LPCOMP = DefaultHandler);
+PROVIDE(SWI0_EGU0 = DefaultHandler);
+PROVIDE(SWI1_EGU1 = DefaultHandler);
+PROVIDE(SWI2_EGU2 = DefaultHandler);
+PROVIDE(SWI3_EGU3 = DefaultHandler);
+PROVIDE(SWI4_EGU4 = DefaultHandler);
+PROVIDE(SWI5_EGU5 = DefaultHandler);
+PROVIDE(TIMER3 = DefaultHandler);
+PROVIDE(TIMER4 = DefaultHandler);
+PROVIDE(PWM0 = DefaultHandler);
+PROVIDE(PDM = DefaultHandler);
+PROVIDE(MWU = DefaultHandler);
+PROVIDE(PWM1 = DefaultHandler);
+PROVIDE(PWM2 = DefaultHandler);
+PROVIDE(SPIM2_SPIS2_SPI2 = DefaultHandler);
+PROVIDE(RTC2 = DefaultHandler);
+PROVIDE(I2S = DefaultHandler);
+PROVIDE(FPU = DefaultHandler);
+PROVIDE(USBD = DefaultHandler);
+PROVIDE(UARTE1 = DefaultHandler);
+PROVIDE(PWM3 = DefaultHandler);
+PROVIDE(SPIM3 = DefaultHandler);
@@ -0,0 +1,366 @@
+//! Bitfield descriptions for registers and the typed accessors generated
+//! from them.
+//!
+//! A field list looks like
+//!
+//! ```text
+//! fields {
+//!     dir: 0 { input = 0, output = 1 },
+//!     pull: 2..=3 { disabled = 0, pulldown = 1, pullup = 3 },
+//!     pin: 8..=12,
+//! }
+//! ```
+//!
+//! and produces a module named after the register holding an `R` and a `W`
+//! type, with one reader and writer proxy per field.
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, LitInt, Token, braced};
+
+pub struct Variant {
+    pub ident: Ident,
+    pub value: LitInt,
+}
+impl Parse for Variant {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        let _: Token![=] = input.parse()?;
+        let value = input.parse()?;
+        Ok(Variant { ident, value })
+    }
+}
+
+pub struct Field {
+    pub ident: Ident,
+    pub lsb: u32,
+    pub msb: u32,
+    pub variants: Vec<Variant>,
+}
+impl Field {
+    fn width(&self) -> u32 {
+        self.msb - self.lsb + 1
+    }
+    fn mask(&self) -> u32 {
+        u32::MAX >> (32 - self.width())
+    }
+}
+fn parse_bit(input: ParseStream) -> syn::Result<(u32, Span)> {
+    let bit: LitInt = input.parse()?;
+    let value = bit.base10_parse::<u32>()?;
+    if value > 31 {
+        return Err(syn::Error::new(
+            bit.span(),
+            "bit index out of range, registers are 32 bits wide",
+        ));
+    }
+    Ok((value, bit.span()))
+}
+impl Parse for Field {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        let _: Token![:] = input.parse()?;
+        let (lsb, lsb_span) = parse_bit(input)?;
+        let msb = if input.peek(Token![..=]) {
+            let _: Token![..=] = input.parse()?;
+            let (msb, msb_span) = parse_bit(input)?;
+            if msb < lsb {
+                return Err(syn::Error::new(
+                    lsb_span.join(msb_span).unwrap_or(msb_span),
+                    "bit range must go from the lowest to the highest bit",
+                ));
+            }
+            msb
+        } else {
+            lsb
+        };
+        let mut field = Field {
+            ident,
+            lsb,
+            msb,
+            variants: Vec::new(),
+        };
+        if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            let variants = Punctuated::<Variant, Token![,]>::parse_terminated(&content)?;
+            for variant in variants.iter() {
+                if variant.value.base10_parse::<u32>()? > field.mask() {
+                    return Err(syn::Error::new(
+                        variant.value.span(),
+                        format!(
+                            "value does not fit in the {} bit(s) of `{}`",
+                            field.width(),
+                            field.ident
+                        ),
+                    ));
+                }
+            }
+            field.variants = variants.into_iter().collect();
+        }
+        Ok(field)
+    }
+}
+
+pub struct Fields {
+    pub fields: Vec<Field>,
+}
+impl Parse for Fields {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let keyword: Ident = input.parse()?;
+        if keyword != "fields" {
+            return Err(syn::Error::new(keyword.span(), "expected `fields`"));
+        }
+        let content;
+        braced!(content in input);
+        let fields: Vec<Field> = Punctuated::<Field, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .collect();
+        let mut taken = 0u32;
+        for field in fields.iter() {
+            let bits = field.mask() << field.lsb;
+            if taken & bits != 0 {
+                return Err(syn::Error::new(
+                    field.ident.span(),
+                    format!("`{}` overlaps the bits of another field", field.ident),
+                ));
+            }
+            taken |= bits;
+        }
+        Ok(Fields { fields })
+    }
+}
+
+/// `pull` => `Pull`, `s0_d1` => `S0D1`, `_08bit` => `_08bit`
+fn upper_camel(ident: &Ident) -> Ident {
+    let camel: String = ident
+        .unraw()
+        .to_string()
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    if camel.starts_with(|c: char| c.is_ascii_digit()) {
+        format_ident!("_{}", camel, span = ident.span())
+    } else {
+        Ident::new(&camel, ident.span())
+    }
+}
+/// Identifier for `name`, raw if it clashes with a keyword and prefixed with
+/// an underscore if it starts with a digit
+pub fn ident(name: &str, span: Span) -> Ident {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        Ident::new(&format!("_{name}"), span)
+    } else {
+        syn::parse_str::<Ident>(name).unwrap_or_else(|_| Ident::new_raw(name, span))
+    }
+}
+/// Lowercase module name for a register
+pub fn module_ident(register: &Ident) -> Ident {
+    ident(
+        &register.unraw().to_string().to_lowercase(),
+        register.span(),
+    )
+}
+fn upper(ident: &Ident) -> String {
+    ident.unraw().to_string().to_uppercase()
+}
+
+fn field_tokens(field: &Field) -> TokenStream2 {
+    let Field {
+        ident, lsb, msb, ..
+    } = field;
+    let reader = format_ident!("{}_R", upper(ident));
+    let writer = format_ident!("{}_W", upper(ident));
+    let mask = field.mask();
+    let doc = if lsb == msb {
+        format!("Field `{}`, bit {lsb}", ident.unraw())
+    } else {
+        format!("Field `{}`, bits {lsb} to {msb}", ident.unraw())
+    };
+    let single_bit = (field.width() == 1).then(|| {
+        quote! {
+            impl #reader {
+                /// Whether the bit is set
+                #[inline]
+                pub fn bit_is_set(&self) -> bool {
+                    self.0 != 0
+                }
+                /// Whether the bit is clear
+                #[inline]
+                pub fn bit_is_clear(&self) -> bool {
+                    self.0 == 0
+                }
+            }
+            impl<'a> #writer<'a> {
+                /// Set the bit
+                #[inline]
+                pub fn set_bit(self) -> &'a mut W {
+                    self.bits(1)
+                }
+                /// Clear the bit
+                #[inline]
+                pub fn clear_bit(self) -> &'a mut W {
+                    self.bits(0)
+                }
+            }
+        }
+    });
+    let variants = (!field.variants.is_empty()).then(|| {
+        let values = format_ident!("{}_A", upper(ident));
+        let names: Vec<Ident> = field
+            .variants
+            .iter()
+            .map(|v| upper_camel(&v.ident))
+            .collect();
+        let methods: Vec<&Ident> = field.variants.iter().map(|v| &v.ident).collect();
+        let checks: Vec<Ident> = methods
+            .iter()
+            .map(|m| format_ident!("is_{}", m.unraw().to_string().trim_start_matches('_')))
+            .collect();
+        let numbers: Vec<&LitInt> = field.variants.iter().map(|v| &v.value).collect();
+        quote! {
+            #[doc = #doc]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #[repr(u32)]
+            pub enum #values {
+                #(#names = #numbers,)*
+            }
+            impl From<#values> for u32 {
+                #[inline]
+                fn from(value: #values) -> Self {
+                    value as u32
+                }
+            }
+            impl #reader {
+                /// The value of the field, if it is one of the known ones
+                #[inline]
+                pub fn variant(&self) -> Option<#values> {
+                    match self.0 {
+                        #(#numbers => Some(#values::#names),)*
+                        _ => None,
+                    }
+                }
+                #(
+                    #[inline]
+                    pub fn #checks(&self) -> bool {
+                        self.0 == #numbers
+                    }
+                )*
+            }
+            impl<'a> #writer<'a> {
+                /// Write one of the known values to the field
+                #[inline]
+                pub fn variant(self, variant: #values) -> &'a mut W {
+                    self.bits(variant.into())
+                }
+                #(
+                    #[inline]
+                    pub fn #methods(self) -> &'a mut W {
+                        self.variant(#values::#names)
+                    }
+                )*
+            }
+        }
+    });
+    quote! {
+        #[doc = #doc]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct #reader(u32);
+        impl #reader {
+            /// Raw value of the field
+            #[inline]
+            pub fn bits(&self) -> u32 {
+                self.0
+            }
+        }
+        #[doc = #doc]
+        pub struct #writer<'a>(&'a mut W);
+        impl<'a> #writer<'a> {
+            /// Write a raw value to the field, bits outside the field are dropped
+            #[inline]
+            pub fn bits(self, value: u32) -> &'a mut W {
+                self.0.0 = (self.0.0 & !(#mask << #lsb)) | ((value & #mask) << #lsb);
+                self.0
+            }
+        }
+        impl R {
+            #[doc = #doc]
+            #[inline]
+            pub fn #ident(&self) -> #reader {
+                #reader((self.0 >> #lsb) & #mask)
+            }
+        }
+        impl W {
+            #[doc = #doc]
+            #[inline]
+            pub fn #ident(&mut self) -> #writer<'_> {
+                #writer(self)
+            }
+        }
+        #single_bit
+        #variants
+    }
+}
+
+/// Module named after the register, holding its `R` and `W` value types
+pub fn module(register: &Ident, fields: Option<&Fields>) -> TokenStream2 {
+    let module = module_ident(register);
+    let doc = format!("Values of the `{register}` register");
+    let fields: TokenStream2 = fields
+        .map(|fields| fields.fields.iter().map(field_tokens).collect())
+        .unwrap_or_default();
+    quote! {
+        #[doc = #doc]
+        #[allow(non_camel_case_types)]
+        pub mod #module {
+            /// Value read from the register
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct R(u32);
+            impl R {
+                /// Raw value of the register
+                #[inline]
+                pub fn bits(&self) -> u32 {
+                    self.0
+                }
+            }
+            impl From<u32> for R {
+                #[inline]
+                fn from(value: u32) -> Self {
+                    R(value)
+                }
+            }
+            /// Value to be written to the register
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct W(u32);
+            impl W {
+                /// Overwrite the whole value
+                #[inline]
+                pub fn bits(&mut self, value: u32) -> &mut Self {
+                    self.0 = value;
+                    self
+                }
+            }
+            impl From<u32> for W {
+                #[inline]
+                fn from(value: u32) -> Self {
+                    W(value)
+                }
+            }
+            impl From<W> for u32 {
+                #[inline]
+                fn from(value: W) -> Self {
+                    value.0
+                }
+            }
+            #fields
+        }
+    }
+}
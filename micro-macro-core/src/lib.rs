@@ -1,9 +1,14 @@
+mod fields;
+mod svd;
+
+use fields::Fields;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{ToTokens, format_ident, quote};
 
-use syn::parse::{Parse, ParseStream};
+use syn::parse::{Parse, ParseStream, Parser};
 use syn::parse_quote;
-use syn::{ExprRange, Ident, ItemStruct, ItemTrait, LitInt};
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprRange, Ident, ItemStruct, ItemTrait, LitInt, RangeLimits};
 
 pub struct AddressableImpl {
     address: LitInt,
@@ -31,22 +36,36 @@ impl ToTokens for AddressableImpl {
     }
 }
 pub fn address(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
-    let res = parse_addressable(attr, item);
-    if let Err(e) = res {
-        return e.to_compile_error();
+    match parse_addressable(attr, item) {
+        Ok(implementor) => quote! {#implementor},
+        Err(e) => e.to_compile_error(),
     }
-
-    let implementor = unsafe { res.unwrap_unchecked() };
-
-    quote! {#implementor}
 }
 pub struct PortImpl {
     port_range: ExprRange,
     implementor: ItemStruct,
 }
+/// `..=31`, the only kind of range a port's `RANGE` can hold
+fn parse_port_range(input: ParseStream) -> syn::Result<ExprRange> {
+    let expr: Expr = input.parse()?;
+    match expr {
+        Expr::Range(
+            range @ ExprRange {
+                start: None,
+                limits: RangeLimits::Closed(_),
+                end: Some(_),
+                ..
+            },
+        ) => Ok(range),
+        other => Err(syn::Error::new_spanned(
+            other,
+            "expected the pins of the port as a range like `..=31`",
+        )),
+    }
+}
 fn parse_port_impl(attr: TokenStream2, item: TokenStream2) -> syn::Result<PortImpl> {
     Ok(PortImpl {
-        port_range: syn::parse2(attr)?,
+        port_range: parse_port_range.parse2(attr)?,
         implementor: syn::parse2(item)?,
     })
 }
@@ -64,13 +83,10 @@ impl ToTokens for PortImpl {
     }
 }
 pub fn port(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
-    let res = parse_port_impl(attr, item);
-    if let Err(e) = res {
-        return e.into_compile_error();
-    };
-
-    let out = unsafe { res.unwrap_unchecked() };
-    quote! {#out}
+    match parse_port_impl(attr, item) {
+        Ok(out) => quote! {#out},
+        Err(e) => e.into_compile_error(),
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -79,17 +95,114 @@ enum RegType {
     Write,
     ReadWrite,
 }
+/// Typed `read`/`write`/`modify` accessors over the register's value types,
+/// taking an element index first when the register is an array
+fn accessors(r#type: RegType, ident: &Ident, count: Option<&LitInt>) -> TokenStream2 {
+    let module = fields::module_ident(ident);
+    let (index, at, raw_read, raw_write) = match count {
+        Some(count) => (
+            quote! { n: usize, },
+            quote! { n, },
+            quote! { <Self as ReadArray<#count>>::read_array_unchecked(n) },
+            quote! { <Self as WriteArray<#count>>::write_array_unchecked(n, w.into()) },
+        ),
+        None => (
+            quote! {},
+            quote! {},
+            quote! { <Self as Read>::read_raw() },
+            quote! { <Self as Write>::write_raw(w.into()) },
+        ),
+    };
+    let read = quote! {
+        /// Read the register
+        #[inline]
+        fn read(#index) -> #module::R {
+            #raw_read.into()
+        }
+    };
+    let write = quote! {
+        /// Write the value built by `f`, starting from all zeroes
+        #[inline]
+        fn write<F>(#index f: F)
+        where
+            F: FnOnce(&mut #module::W) -> &mut #module::W,
+        {
+            let mut w = #module::W::from(0);
+            f(&mut w);
+            #raw_write
+        }
+    };
+    let modify = quote! {
+        /// Read the register and write back the value as changed by `f`
+        #[inline]
+        fn modify<F>(#index f: F)
+        where
+            F: for<'w> FnOnce(&#module::R, &'w mut #module::W) -> &'w mut #module::W,
+        {
+            let r = <Self as #ident>::read(#at);
+            let mut w = #module::W::from(r.bits());
+            f(&r, &mut w);
+            #raw_write
+        }
+    };
+    match r#type {
+        RegType::Read => read,
+        RegType::Write => write,
+        RegType::ReadWrite => quote! { #read #write #modify },
+    }
+}
 fn reg_trait(r#type: RegType, ident: Ident) -> ItemTrait {
     let bounds = match r#type {
         RegType::Read => quote! { Read },
         RegType::Write => quote! { Write },
         RegType::ReadWrite => quote! { Read + Write },
     };
-    parse_quote! { pub trait #ident: #bounds {} }
+    let accessors = accessors(r#type, &ident, None);
+    parse_quote! {
+        #[allow(non_camel_case_types)]
+        pub trait #ident: #bounds {
+            #accessors
+        }
+    }
+}
+/// Suffix of the banks belonging to `instance`: its trailing digits, so
+/// `UARTE1` gets `1`, or its position in the instance list if it has none
+fn bank_suffix(instance: &Ident, index: usize) -> String {
+    let name = instance.to_string();
+    let digits = &name[name.trim_end_matches(|c: char| c.is_ascii_digit()).len()..];
+    if digits.is_empty() {
+        index.to_string()
+    } else {
+        digits.to_string()
+    }
+}
+/// Report the first instance whose bank suffix is taken by an earlier one,
+/// as happens when numbered and unnumbered instances are mixed, `[A1, B]`
+fn check_bank_suffixes<'a>(instances: impl IntoIterator<Item = &'a Ident>) -> syn::Result<()> {
+    let mut seen: Vec<(String, &Ident)> = Vec::new();
+    for (index, instance) in instances.into_iter().enumerate() {
+        let suffix = bank_suffix(instance, index);
+        if let Some((_, first)) = seen.iter().find(|(taken, _)| *taken == suffix) {
+            return Err(syn::Error::new(
+                instance.span(),
+                format!(
+                    "the banks of `{instance}` would get the suffix `{suffix}` of `{first}`, \
+                     number all instances or none"
+                ),
+            ));
+        }
+        seen.push((suffix, instance));
+    }
+    Ok(())
 }
-fn bank(r#type: RegType, bank_num: u32, ident: Ident, offset: LitInt) -> TokenStream2 {
-    let port_ident = format_ident! {"P{bank_num}"};
-    let bank_ident = format_ident! {"{ident}{bank_num}"};
+fn bank(
+    r#type: RegType,
+    instance: &Ident,
+    suffix: &str,
+    ident: &Ident,
+    offset: &LitInt,
+) -> TokenStream2 {
+    let bank_ident = format_ident! {"{ident}{suffix}"};
     let implementations = match r#type {
         RegType::Read => quote! {impl Read for #bank_ident {}},
         RegType::Write => quote! {impl Write for #bank_ident {}},
@@ -99,42 +212,146 @@ fn bank(r#type: RegType, bank_num: u32, ident: Ident, offset: LitInt) -> TokenSt
         },
     };
     quote! {
+        #[allow(non_camel_case_types)]
         pub struct #bank_ident;
         impl #ident for #bank_ident {}
         impl Register for #bank_ident {
-            type Port = #port_ident;
+            type Instance = #instance;
+            const OFFSET: usize = #offset;
+        }
+        #implementations
+    }
+}
+fn array_trait(r#type: RegType, ident: &Ident, count: &LitInt) -> ItemTrait {
+    let bounds = match r#type {
+        RegType::Read => quote! { ReadArray<#count> },
+        RegType::Write => quote! { WriteArray<#count> },
+        RegType::ReadWrite => quote! { ReadArray<#count> + WriteArray<#count> },
+    };
+    let accessors = accessors(r#type, ident, Some(count));
+    parse_quote! {
+        #[allow(non_camel_case_types)]
+        pub trait #ident: #bounds {
+            #accessors
+        }
+    }
+}
+fn array_bank(
+    r#type: RegType,
+    instance: &Ident,
+    suffix: &str,
+    ident: &Ident,
+    offset: &LitInt,
+    count: &LitInt,
+    stride: &LitInt,
+) -> TokenStream2 {
+    let bank_ident = format_ident! {"{ident}{suffix}"};
+    let implementations = match r#type {
+        RegType::Read => quote! {impl ReadArray<#count> for #bank_ident {}},
+        RegType::Write => quote! {impl WriteArray<#count> for #bank_ident {}},
+        RegType::ReadWrite => quote! {
+            impl ReadArray<#count> for #bank_ident {}
+            impl WriteArray<#count> for #bank_ident {}
+        },
+    };
+    quote! {
+        #[allow(non_camel_case_types)]
+        pub struct #bank_ident;
+        impl #ident for #bank_ident {}
+        impl RegisterArray<#count> for #bank_ident {
+            type Instance = #instance;
             const OFFSET: usize = #offset;
+            const STRIDE: usize = #stride;
         }
         #implementations
+        impl core::ops::Index<usize> for #bank_ident {
+            type Output = usize;
+            fn index(&self, index: usize) -> &Self::Output {
+                &<Self as RegisterArray<#count>>::ADDRS[index]
+            }
+        }
+    }
+}
+fn parse_reg_type(input: ParseStream) -> syn::Result<RegType> {
+    match input.parse::<Ident>() {
+        Ok(val) if val == "Read" => Ok(RegType::Read),
+        Ok(val) if val == "Write" => Ok(RegType::Write),
+        Ok(val) if val == "ReadWrite" => Ok(RegType::ReadWrite),
+        Ok(val) => Err(syn::Error::new(
+            val.span(),
+            "unrecognized register type, specify either Read, Write, or ReadWrite",
+        )),
+        Err(e) => Err(e),
+    }
+}
+/// Trailing `, fields { .. }`, if present
+fn parse_fields(input: ParseStream) -> syn::Result<Option<Fields>> {
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let _: syn::Token![,] = input.parse()?;
+    if input.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(input.parse()?))
+}
+/// `, 0x504`
+fn parse_offset(input: ParseStream) -> syn::Result<LitInt> {
+    const EXPECTED: &str = "expected the offset of the register, e.g. `0x504`";
+    if input.is_empty() {
+        return Err(input.error(EXPECTED));
+    }
+    let _: syn::Token![,] = input.parse()?;
+    input
+        .parse()
+        .map_err(|e| syn::Error::new(e.span(), EXPECTED))
+}
+/// `, instances = [P0, P1]`
+fn parse_instances(input: ParseStream) -> syn::Result<Vec<Ident>> {
+    if input.is_empty() {
+        return Err(input.error("expected the instances of the register, e.g. `instances = [P0]`"));
+    }
+    let _: syn::Token![,] = input.parse()?;
+    let key: Ident = input.parse()?;
+    if key != "instances" {
+        return Err(syn::Error::new(key.span(), "expected `instances`"));
+    }
+    let _: syn::Token![=] = input.parse()?;
+    let content;
+    let brackets = syn::bracketed!(content in input);
+    let instances: Vec<Ident> = Punctuated::<Ident, syn::Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .collect();
+    if instances.is_empty() {
+        return Err(syn::Error::new(
+            brackets.span.join(),
+            "expected at least one instance",
+        ));
     }
+    check_bank_suffixes(&instances)?;
+    Ok(instances)
 }
 pub struct RegAttrs {
     ident: Ident,
     r#type: RegType,
     offset: LitInt,
+    instances: Vec<Ident>,
+    fields: Option<Fields>,
 }
 impl Parse for RegAttrs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let ident = input.parse()?;
         let _: syn::Token![,] = input.parse()?;
-        let r#type = match input.parse::<Ident>() {
-            Ok(val) if val == "Read" => RegType::Read,
-            Ok(val) if val == "Write" => RegType::Write,
-            Ok(val) if val == "ReadWrite" => RegType::ReadWrite,
-            Ok(val) => {
-                return Err(syn::Error::new(
-                    val.span(),
-                    "unrecognized register type, specity either Read, Write, or ReadWrite",
-                ));
-            }
-            Err(e) => return Err(e),
-        };
-        let _: syn::Token![,] = input.parse()?;
-        let offset: LitInt = input.parse()?;
+        let r#type = parse_reg_type(input)?;
+        let offset = parse_offset(input)?;
+        let instances = parse_instances(input)?;
+        let fields = parse_fields(input)?;
         Ok(RegAttrs {
             ident,
             r#type,
             offset,
+            instances,
+            fields,
         })
     }
 }
@@ -153,23 +370,295 @@ impl ToTokens for Reg {
                     ident,
                     r#type,
                     offset,
+                    instances,
+                    fields,
                 },
         } = self;
+        let module = fields::module(ident, fields.as_ref());
         let trait_def = reg_trait(*r#type, ident.clone());
-        let bank_def_0 = bank(*r#type, 0, ident.clone(), offset.clone());
-        let bank_def_1 = bank(*r#type, 1, ident.clone(), offset.clone());
+        let banks = instances.iter().enumerate().map(|(index, instance)| {
+            bank(
+                *r#type,
+                instance,
+                &bank_suffix(instance, index),
+                ident,
+                offset,
+            )
+        });
         tokens.extend(quote! {
+            #module
             #trait_def
-            #bank_def_0
-            #bank_def_1
+            #(#banks)*
         });
     }
 }
 pub fn reg(attr: TokenStream2) -> TokenStream2 {
-    let res = parse_reg(attr);
-    if let Err(e) = res {
-        return e.to_compile_error();
+    match parse_reg(attr) {
+        Ok(out) => quote! {#out},
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+/// `key = value`
+fn parse_keyed_int(input: ParseStream, key: &str) -> syn::Result<LitInt> {
+    let ident: Ident = input.parse()?;
+    if ident != key {
+        return Err(syn::Error::new(ident.span(), format!("expected `{key}`")));
+    }
+    let _: syn::Token![=] = input.parse()?;
+    input.parse()
+}
+pub struct RegArrayAttrs {
+    ident: Ident,
+    r#type: RegType,
+    offset: LitInt,
+    count: LitInt,
+    stride: LitInt,
+    instances: Vec<Ident>,
+    fields: Option<Fields>,
+}
+impl Parse for RegArrayAttrs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        let _: syn::Token![,] = input.parse()?;
+        let r#type = parse_reg_type(input)?;
+        let offset = parse_offset(input)?;
+        let _: syn::Token![,] = input.parse()?;
+        let count = parse_keyed_int(input, "count")?;
+        if count.base10_parse::<usize>()? == 0 {
+            return Err(syn::Error::new(
+                count.span(),
+                "a register array needs at least one element",
+            ));
+        }
+        let stride = if input.peek(syn::Token![,]) && input.peek2(Ident) && {
+            let fork = input.fork();
+            let _: syn::Token![,] = fork.parse()?;
+            fork.parse::<Ident>()? == "stride"
+        } {
+            let _: syn::Token![,] = input.parse()?;
+            parse_keyed_int(input, "stride")?
+        } else {
+            LitInt::new("4", count.span())
+        };
+        let instances = parse_instances(input)?;
+        let fields = parse_fields(input)?;
+        Ok(RegArrayAttrs {
+            ident,
+            r#type,
+            offset,
+            count,
+            stride,
+            instances,
+            fields,
+        })
+    }
+}
+pub struct RegArray {
+    attrs: RegArrayAttrs,
+}
+impl ToTokens for RegArray {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let RegArray {
+            attrs:
+                RegArrayAttrs {
+                    ident,
+                    r#type,
+                    offset,
+                    count,
+                    stride,
+                    instances,
+                    fields,
+                },
+        } = self;
+        let module = fields::module(ident, fields.as_ref());
+        let trait_def = array_trait(*r#type, ident, count);
+        let banks = instances.iter().enumerate().map(|(index, instance)| {
+            let suffix = bank_suffix(instance, index);
+            array_bank(*r#type, instance, &suffix, ident, offset, count, stride)
+        });
+        tokens.extend(quote! {
+            #module
+            #trait_def
+            #(#banks)*
+        });
+    }
+}
+pub fn reg_array(attr: TokenStream2) -> TokenStream2 {
+    match syn::parse2::<RegArrayAttrs>(attr) {
+        Ok(attrs) => RegArray { attrs }.into_token_stream(),
+        Err(e) => e.to_compile_error(),
+    }
+}
+pub fn svd(attr: TokenStream2) -> TokenStream2 {
+    match syn::parse2::<svd::Svd>(attr).and_then(|svd| svd.expand()) {
+        Ok(out) => out,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Expanded tokens, formatted for a readable snapshot
+    fn pretty(tokens: TokenStream2) -> String {
+        prettyplease::unparse(&syn::parse2(tokens).unwrap())
+    }
+    /// Parse `source` so its tokens carry line and column information
+    fn tokens(source: &str) -> TokenStream2 {
+        TokenStream2::from_str(source).unwrap()
+    }
+    /// Message and start column of the error `reg!` reports for `source`
+    fn reg_error(source: &str) -> (String, usize) {
+        match parse_reg(tokens(source)) {
+            Ok(_) => panic!("`{source}` parsed"),
+            Err(e) => (e.to_string(), e.span().start().column),
+        }
+    }
+
+    #[test]
+    fn address_expansion() {
+        insta::assert_snapshot!(pretty(address(
+            quote! { 0x5000_0000 },
+            quote! { pub struct P0; },
+        )));
+    }
+
+    #[test]
+    fn port_expansion() {
+        insta::assert_snapshot!(pretty(port(quote! { ..=31 }, quote! { pub struct P0; },)));
+    }
+
+    #[test]
+    fn reg_expansion() {
+        insta::assert_snapshot!(pretty(reg(quote! {
+            OUT, ReadWrite, 0x504, instances = [P0, P1]
+        })));
+    }
+
+    #[test]
+    fn reg_with_fields_expansion() {
+        insta::assert_snapshot!(pretty(reg(quote! {
+            DETECTMODE, ReadWrite, 0x524, instances = [P0], fields {
+                detectmode: 0 { default = 0, ldetect = 1 },
+            }
+        })));
+    }
+
+    #[test]
+    fn reg_array_expansion() {
+        insta::assert_snapshot!(pretty(reg_array(quote! {
+            CH_EEP, Write, 0x510, count = 20, stride = 8, instances = [PPI]
+        })));
+    }
+
+    #[test]
+    fn bad_register_type_is_reported_at_the_type() {
+        assert_eq!(
+            reg_error("OUT, ReadOnly, 0x504, instances = [P0]"),
+            (
+                "unrecognized register type, specify either Read, Write, or ReadWrite".into(),
+                "OUT, ".len()
+            )
+        );
+    }
+
+    #[test]
+    fn missing_offset_is_reported() {
+        assert_eq!(
+            reg_error("OUT, Read"),
+            (
+                "unexpected end of input, expected the offset of the register, e.g. `0x504`".into(),
+                0
+            )
+        );
+        let source = "OUT, Read, instances = [P0]";
+        assert_eq!(
+            reg_error(source),
+            (
+                "expected the offset of the register, e.g. `0x504`".into(),
+                source.find("instances").unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn missing_instances_are_reported() {
+        assert_eq!(
+            reg_error("OUT, Read, 0x510"),
+            (
+                "unexpected end of input, expected the instances of the register, e.g. `instances = [P0]`"
+                    .into(),
+                0
+            )
+        );
+        let source = "OUT, Read, 0x510, instances = []";
+        assert_eq!(
+            reg_error(source),
+            (
+                "expected at least one instance".into(),
+                source.find('[').unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn clashing_bank_suffixes_are_reported_at_the_instance() {
+        let source = "OUT, Read, 0x510, instances = [A1, B]";
+        assert_eq!(
+            reg_error(source),
+            (
+                "the banks of `B` would get the suffix `1` of `A1`, number all instances or none"
+                    .into(),
+                source.find('B').unwrap()
+            )
+        );
+        let e = syn::parse2::<RegArrayAttrs>(tokens(
+            "CH, Read, 0x510, count = 2, stride = 4, instances = [A, B0]",
+        ))
+        .err()
+        .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "the banks of `B0` would get the suffix `0` of `A`, number all instances or none"
+        );
+    }
+
+    #[test]
+    fn non_range_port_is_reported_at_the_attribute() {
+        let e = parse_port_impl(tokens("31"), quote! { pub struct P0; })
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "expected the pins of the port as a range like `..=31`"
+        );
+        assert_eq!(e.span().start().column, 0);
+        let e = parse_port_impl(tokens("0..32"), quote! { pub struct P0; })
+            .err()
+            .unwrap();
+        assert_eq!((e.span().start().column, e.span().end().column), (0, 5));
+    }
+
+    #[test]
+    fn field_errors_point_at_the_field() {
+        let source = "PINCNF, ReadWrite, 0x700, instances = [P0], fields { pull: 3..=2, }";
+        assert_eq!(
+            reg_error(source),
+            (
+                "bit range must go from the lowest to the highest bit".into(),
+                source.find("3..=2").unwrap()
+            )
+        );
+        let source = "PINCNF, ReadWrite, 0x700, instances = [P0], fields { dir: 0 { output = 2 } }";
+        assert_eq!(
+            reg_error(source),
+            (
+                "value does not fit in the 1 bit(s) of `dir`".into(),
+                source.find('2').unwrap()
+            )
+        );
     }
-    let out = unsafe { res.unwrap_unchecked() };
-    quote! {#out}
 }
@@ -0,0 +1,454 @@
+//! Peripheral definitions read from a CMSIS-SVD device description.
+//!
+//! ```text
+//! svd! { "svd/nrf52833.svd", P0 { port = ..=31 }, P1 { port = ..=9 } }
+//! ```
+//!
+//! defines the listed peripheral instances at the base addresses given in the
+//! file, and every register of the peripheral they share, fields included, as
+//! if each had been written out with `reg!`. Registers declared as `NAME[%s]`
+//! become register arrays, and a register named like one of the instances
+//! gets a `_REG` suffix. The path is relative to the manifest of the crate
+//! invoking the macro.
+use std::collections::HashSet;
+use std::path::Path;
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{ToTokens, format_ident, quote};
+use roxmltree::Node;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{ExprRange, Ident, LitInt, LitStr, Token, braced};
+
+use crate::fields::{self, Field, Fields, Variant};
+use crate::{RegType, array_bank, array_trait, bank, bank_suffix, reg_trait};
+
+/// A peripheral instance to define, `port = ..` making it a GPIO port
+struct Instance {
+    ident: Ident,
+    port: Option<ExprRange>,
+}
+impl Parse for Instance {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        let mut port = None;
+        if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            let key: Ident = content.parse()?;
+            if key != "port" {
+                return Err(syn::Error::new(key.span(), "expected `port`"));
+            }
+            let _: Token![=] = content.parse()?;
+            port = Some(crate::parse_port_range(&content)?);
+        }
+        Ok(Instance { ident, port })
+    }
+}
+
+pub struct Svd {
+    path: LitStr,
+    instances: Vec<Instance>,
+}
+impl Parse for Svd {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let instances: Vec<Instance> = Punctuated::<Instance, Token![,]>::parse_terminated(input)?
+            .into_iter()
+            .collect();
+        if instances.is_empty() {
+            return Err(input.error("expected at least one peripheral instance"));
+        }
+        crate::check_bank_suffixes(instances.iter().map(|i| &i.ident))?;
+        Ok(Svd { path, instances })
+    }
+}
+
+/// A register, flattened out of any clusters it is nested in
+struct Register {
+    ident: Ident,
+    description: String,
+    offset: u64,
+    r#type: RegType,
+    /// Element count and stride of a register array
+    dim: Option<(u64, u64)>,
+    fields: Option<Fields>,
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+fn text<'a>(node: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(node, tag).and_then(|n| n.text()).map(str::trim)
+}
+/// `0x10`, `#0101` or `16`
+fn parse_int(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = text.strip_prefix('#') {
+        u64::from_str_radix(bin, 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+fn int(node: Node, tag: &str) -> Option<u64> {
+    text(node, tag).and_then(parse_int)
+}
+fn description(node: Node) -> String {
+    text(node, "description")
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+fn access(node: Node, default: RegType) -> RegType {
+    match text(node, "access") {
+        Some("read-only") => RegType::Read,
+        Some("write-only") => RegType::Write,
+        Some(_) => RegType::ReadWrite,
+        None => default,
+    }
+}
+
+fn variants(field: Node, span: Span) -> Vec<Variant> {
+    let mut names = HashSet::new();
+    let mut values = HashSet::new();
+    field
+        .descendants()
+        .filter(|n| n.has_tag_name("enumeratedValue"))
+        .filter_map(|n| Some((text(n, "name")?.to_lowercase(), int(n, "value")?)))
+        .filter(|(name, value)| names.insert(name.clone()) && values.insert(*value))
+        .map(|(name, value)| Variant {
+            ident: fields::ident(&name, span),
+            value: LitInt::new(&value.to_string(), span),
+        })
+        .collect()
+}
+fn field(node: Node, span: Span) -> Option<Field> {
+    let (lsb, msb) = match (int(node, "lsb"), int(node, "msb")) {
+        (Some(lsb), Some(msb)) => (lsb, msb),
+        _ => {
+            let offset = int(node, "bitOffset")?;
+            (offset, offset + int(node, "bitWidth")? - 1)
+        }
+    };
+    Some(Field {
+        ident: fields::ident(&text(node, "name")?.to_lowercase(), span),
+        lsb: lsb as u32,
+        msb: msb as u32,
+        variants: variants(node, span),
+    })
+}
+fn register_fields(node: Node, span: Span) -> Option<Fields> {
+    let fields: Vec<Field> = child(node, "fields")?
+        .children()
+        .filter(|n| n.has_tag_name("field"))
+        .filter_map(|n| field(n, span))
+        .collect();
+    (!fields.is_empty()).then_some(Fields { fields })
+}
+
+/// Array dimensions of `node` as `(count, stride)`, if it is one
+fn dim(node: Node) -> Option<(u64, u64)> {
+    Some((int(node, "dim")?, int(node, "dimIncrement")?))
+}
+/// Collect the registers below `node`, prefixing the names of those nested in
+/// a cluster with the cluster's name
+///
+/// Register arrays have a single dimension, so an array nested in an array
+/// cluster is reported at `peripheral` rather than left out.
+fn collect(
+    node: Node,
+    prefix: &str,
+    base: u64,
+    outer: Option<(u64, u64)>,
+    default: RegType,
+    peripheral: &Ident,
+    out: &mut Vec<Register>,
+) -> syn::Result<()> {
+    let span = peripheral.span();
+    for child in node.children().filter(|n| n.is_element()) {
+        let (Some(name), Some(offset)) = (text(child, "name"), int(child, "addressOffset")) else {
+            continue;
+        };
+        let name = format!("{prefix}{}", name.replace("[%s]", ""));
+        let dim = match (outer, dim(child)) {
+            (Some(_), Some(_)) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "`{name}` of `{peripheral}` is an array nested in an array, \
+                         which is not supported"
+                    ),
+                ));
+            }
+            (outer, inner) => outer.or(inner),
+        };
+        let default = access(child, default);
+        if child.has_tag_name("cluster") {
+            let prefix = format!("{name}_");
+            collect(child, &prefix, base + offset, dim, default, peripheral, out)?;
+        } else if child.has_tag_name("register") {
+            out.push(Register {
+                ident: Ident::new(&name, span),
+                description: description(child),
+                offset: base + offset,
+                r#type: default,
+                dim,
+                fields: register_fields(child, span),
+            });
+        }
+    }
+    Ok(())
+}
+
+impl Svd {
+    pub fn expand(&self) -> syn::Result<TokenStream2> {
+        let manifest = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+        let path = Path::new(&manifest).join(self.path.value());
+        let source = std::fs::read_to_string(&path).map_err(|e| {
+            syn::Error::new(
+                self.path.span(),
+                format!("could not read `{}`: {e}", path.display()),
+            )
+        })?;
+        let device = roxmltree::Document::parse(&source)
+            .map_err(|e| syn::Error::new(self.path.span(), format!("invalid SVD file: {e}")))?;
+        let peripherals: Vec<Node> = device
+            .descendants()
+            .filter(|n| n.has_tag_name("peripheral"))
+            .collect();
+        let find = |name: &str| {
+            peripherals
+                .iter()
+                .copied()
+                .find(|p| text(*p, "name") == Some(name))
+        };
+
+        let span = self.path.span();
+        let mut instances = TokenStream2::new();
+        let mut registers_of = None;
+        for Instance { ident, port } in self.instances.iter() {
+            let peripheral = find(&ident.to_string()).ok_or_else(|| {
+                syn::Error::new(
+                    ident.span(),
+                    format!("no peripheral `{ident}` in the SVD file"),
+                )
+            })?;
+            let base = match peripheral.attribute("derivedFrom") {
+                Some(name) => find(name).ok_or_else(|| {
+                    syn::Error::new(
+                        ident.span(),
+                        format!("`{ident}` derives from unknown `{name}`"),
+                    )
+                })?,
+                None => peripheral,
+            };
+            match registers_of {
+                None => registers_of = Some(base),
+                Some(first) if first == base => {}
+                Some(first) => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        format!(
+                            "`{ident}` does not share its registers with `{}`",
+                            text(first, "name").unwrap_or_default()
+                        ),
+                    ));
+                }
+            }
+            let address = int(peripheral, "baseAddress").ok_or_else(|| {
+                syn::Error::new(ident.span(), format!("`{ident}` has no base address"))
+            })?;
+            let address = LitInt::new(&format!("{address:#x}"), span);
+            let doc = match description(peripheral) {
+                doc if doc.is_empty() => description(base),
+                doc => doc,
+            };
+            let port = port.as_ref().map(|range| {
+                quote! {
+                    impl Port for #ident {
+                        const RANGE: core::ops::RangeToInclusive<u8> = #range;
+                    }
+                }
+            });
+            instances.extend(quote! {
+                #[doc = #doc]
+                #[non_exhaustive]
+                pub struct #ident;
+                impl Addressable for #ident {
+                    const ADDR: usize = #address;
+                }
+                #port
+            });
+        }
+
+        let mut registers = Vec::new();
+        if let Some(block) = registers_of.and_then(|p| child(p, "registers")) {
+            let default = access(registers_of.unwrap(), RegType::ReadWrite);
+            let first = &self.instances[0].ident;
+            collect(block, "", 0, None, default, first, &mut registers)?;
+        }
+        // `TEMP.TEMP` and the like
+        for register in registers.iter_mut() {
+            if self.instances.iter().any(|i| i.ident == register.ident) {
+                register.ident = format_ident!("{}_REG", register.ident);
+            }
+        }
+        let suffixes: Vec<String> = self
+            .instances
+            .iter()
+            .enumerate()
+            .map(|(index, instance)| bank_suffix(&instance.ident, index))
+            .collect();
+        let mut definitions = TokenStream2::new();
+        for register in registers.iter() {
+            let Register {
+                ident,
+                description,
+                offset,
+                r#type,
+                dim,
+                fields,
+            } = register;
+            let offset = LitInt::new(&format!("{offset:#x}"), span);
+            let dim = dim.map(|(count, stride)| {
+                (
+                    LitInt::new(&count.to_string(), span),
+                    LitInt::new(&format!("{stride:#x}"), span),
+                )
+            });
+            definitions.extend(fields::module(ident, fields.as_ref()));
+            let mut trait_def = match &dim {
+                Some((count, _)) => array_trait(*r#type, ident, count),
+                None => reg_trait(*r#type, ident.clone()),
+            };
+            if !description.is_empty() {
+                trait_def
+                    .attrs
+                    .push(syn::parse_quote!(#[doc = #description]));
+            }
+            trait_def.to_tokens(&mut definitions);
+            for (instance, suffix) in self.instances.iter().zip(suffixes.iter()) {
+                definitions.extend(match &dim {
+                    Some((count, stride)) => array_bank(
+                        *r#type,
+                        &instance.ident,
+                        suffix,
+                        ident,
+                        &offset,
+                        count,
+                        stride,
+                    ),
+                    None => bank(*r#type, &instance.ident, suffix, ident, &offset),
+                });
+            }
+        }
+
+        let path = path.display().to_string();
+        Ok(quote! {
+            const _: &[u8] = include_bytes!(#path);
+            #instances
+            #definitions
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVD: &str = "../svd/nrf52833.svd";
+
+    fn expand(instances: TokenStream2) -> syn::Result<TokenStream2> {
+        let svd: Svd = syn::parse2(quote! { #SVD, #instances })?;
+        svd.expand()
+    }
+
+    #[test]
+    fn peripherals_expand_to_items() {
+        for instances in [
+            quote! { P0 { port = ..=31 }, P1 { port = ..=9 } },
+            quote! { UARTE0, UARTE1 },
+            quote! { TIMER0, TIMER1, TIMER2 },
+            quote! { PPI },
+            quote! { RADIO },
+        ] {
+            let tokens = expand(instances.clone()).unwrap();
+            if let Err(e) = syn::parse2::<syn::File>(tokens) {
+                panic!("{instances}: {e}");
+            }
+        }
+    }
+
+    #[test]
+    fn register_named_like_its_peripheral_is_renamed() {
+        let tokens = expand(quote! { TEMP }).unwrap().to_string();
+        assert!(tokens.contains("pub trait TEMP_REG"));
+        assert!(tokens.contains("impl TEMP_REG for TEMP_REG0"));
+    }
+
+    #[test]
+    fn pin_cnf_becomes_a_register_array() {
+        let tokens = expand(quote! { P0 { port = ..=31 } }).unwrap().to_string();
+        assert!(tokens.contains("impl RegisterArray < 32 > for PIN_CNF0"));
+        assert!(tokens.contains("const OFFSET : usize = 0x700"));
+        assert!(tokens.contains("const ADDR : usize = 0x50000000"));
+    }
+
+    #[test]
+    fn cluster_arrays_keep_their_stride() {
+        let tokens = expand(quote! { PPI }).unwrap().to_string();
+        assert!(tokens.contains("impl RegisterArray < 20 > for CH_EEP0"));
+        assert!(tokens.contains("const OFFSET : usize = 0x510 ; const STRIDE : usize = 0x8"));
+    }
+
+    #[test]
+    fn instances_must_share_registers() {
+        let e = expand(quote! { P0, UARTE1 }).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "`UARTE1` does not share its registers with `P0`"
+        );
+        let e = expand(quote! { P2 }).unwrap_err();
+        assert_eq!(e.to_string(), "no peripheral `P2` in the SVD file");
+    }
+
+    #[test]
+    fn instances_must_not_share_bank_suffixes() {
+        let e = expand(quote! { TIMER1, GPIOTE }).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "the banks of `GPIOTE` would get the suffix `1` of `TIMER1`, number all instances or none"
+        );
+    }
+
+    #[test]
+    fn nested_arrays_are_an_error() {
+        let path = std::env::temp_dir().join("micro-macro-core-nested-dim.svd");
+        std::fs::write(
+            &path,
+            r#"<device><peripherals><peripheral>
+                <name>NESTED</name><baseAddress>0x40000000</baseAddress>
+                <registers><cluster>
+                    <dim>4</dim><dimIncrement>0x20</dimIncrement>
+                    <name>CH[%s]</name><addressOffset>0x100</addressOffset>
+                    <register>
+                        <dim>2</dim><dimIncrement>4</dimIncrement>
+                        <name>LIMIT[%s]</name><addressOffset>0x0</addressOffset>
+                    </register>
+                </cluster></registers>
+            </peripheral></peripherals></device>"#,
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let svd: Svd = syn::parse2(quote! { #path, NESTED }).unwrap();
+        let e = svd.expand().unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "`CH_LIMIT` of `NESTED` is an array nested in an array, which is not supported"
+        );
+    }
+}
@@ -8,7 +8,106 @@ pub fn address(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn port(attr: TokenStream, item: TokenStream) -> TokenStream {
     micro_macro_core::port(attr.into(), item.into()).into()
 }
+/// Define a register at an offset from each of its `instances`, with one
+/// bank per instance
+///
+/// The access mode decides which of `Read` and `Write` the banks implement,
+/// so a read-only register can't be written and a write-only one can't be
+/// read:
+///
+/// ```
+/// # use microrust::{
+/// #     Addressable, Port, Read, ReadArray, Register, RegisterArray, Write, WriteArray,
+/// # };
+/// # pub struct P0;
+/// # impl Addressable for P0 {
+/// #     const ADDR: usize = 0x5000_0000;
+/// # }
+/// # impl Port for P0 {
+/// #     const RANGE: core::ops::RangeToInclusive<u8> = ..=31;
+/// # }
+/// micro_macro::reg! {OUT, ReadWrite, 0x504, instances = [P0]}
+///
+/// OUT0::write_raw(OUT0::read_raw() | 1);
+/// ```
+///
+/// ```compile_fail,E0599
+/// # use microrust::{
+/// #     Addressable, Port, Read, ReadArray, Register, RegisterArray, Write, WriteArray,
+/// # };
+/// # pub struct P0;
+/// # impl Addressable for P0 {
+/// #     const ADDR: usize = 0x5000_0000;
+/// # }
+/// # impl Port for P0 {
+/// #     const RANGE: core::ops::RangeToInclusive<u8> = ..=31;
+/// # }
+/// micro_macro::reg! {IN, Read, 0x510, instances = [P0]}
+///
+/// IN0::write_raw(1);
+/// ```
+///
+/// ```compile_fail,E0599
+/// # use microrust::{
+/// #     Addressable, Port, Read, ReadArray, Register, RegisterArray, Write, WriteArray,
+/// # };
+/// # pub struct P0;
+/// # impl Addressable for P0 {
+/// #     const ADDR: usize = 0x5000_0000;
+/// # }
+/// # impl Port for P0 {
+/// #     const RANGE: core::ops::RangeToInclusive<u8> = ..=31;
+/// # }
+/// micro_macro::reg! {OUTSET, Write, 0x508, instances = [P0]}
+///
+/// OUTSET0::read_raw();
+/// ```
 #[proc_macro]
 pub fn reg(attr: TokenStream) -> TokenStream {
     micro_macro_core::reg(attr.into()).into()
 }
+/// Define an array of `count` registers, `stride` bytes apart, like
+/// [`reg!`]
+///
+/// The access mode decides between `ReadArray` and `WriteArray` the same
+/// way:
+///
+/// ```compile_fail,E0599
+/// # use microrust::{
+/// #     Addressable, Port, Read, ReadArray, Register, RegisterArray, Write, WriteArray,
+/// # };
+/// # pub struct P0;
+/// # impl Addressable for P0 {
+/// #     const ADDR: usize = 0x5000_0000;
+/// # }
+/// # impl Port for P0 {
+/// #     const RANGE: core::ops::RangeToInclusive<u8> = ..=31;
+/// # }
+/// micro_macro::reg_array! {LATCHES, Read, 0x700, count = 4, instances = [P0]}
+///
+/// LATCHES0::write_array(0, 1);
+/// ```
+///
+/// ```compile_fail,E0599
+/// # use microrust::{
+/// #     Addressable, Port, Read, ReadArray, Register, RegisterArray, Write, WriteArray,
+/// # };
+/// # pub struct P0;
+/// # impl Addressable for P0 {
+/// #     const ADDR: usize = 0x5000_0000;
+/// # }
+/// # impl Port for P0 {
+/// #     const RANGE: core::ops::RangeToInclusive<u8> = ..=31;
+/// # }
+/// micro_macro::reg_array! {TASKS, Write, 0x000, count = 4, instances = [P0]}
+///
+/// TASKS0::read_array(0);
+/// ```
+#[proc_macro]
+pub fn reg_array(attr: TokenStream) -> TokenStream {
+    micro_macro_core::reg_array(attr.into()).into()
+}
+#[proc_macro]
+pub fn svd(attr: TokenStream) -> TokenStream {
+    micro_macro_core::svd(attr.into()).into()
+}
@@ -0,0 +1,43 @@
+//! The access mode of a register decides which of `Read`, `Write` and their
+//! array variants its banks implement, and so which accessors they have.
+use microrust::{Addressable, Port, Read, ReadArray, Register, RegisterArray, Write, WriteArray};
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+pub struct P0;
+impl Addressable for P0 {
+    const ADDR: usize = 0x5000_0000;
+}
+impl Port for P0 {
+    const RANGE: core::ops::RangeToInclusive<u8> = ..=31;
+}
+pub struct PPI;
+impl Addressable for PPI {
+    const ADDR: usize = 0x4001_F000;
+}
+
+micro_macro::reg! {IN, Read, 0x510, instances = [P0]}
+micro_macro::reg! {OUTSET, Write, 0x508, instances = [P0]}
+micro_macro::reg! {OUT, ReadWrite, 0x504, instances = [P0]}
+micro_macro::reg_array! {CH_EEP, Read, 0x510, count = 20, stride = 8, instances = [PPI]}
+micro_macro::reg_array! {TASKS_CHG_EN, Write, 0x000, count = 6, stride = 8, instances = [PPI]}
+micro_macro::reg_array! {PIN_CNF, ReadWrite, 0x700, count = 32, instances = [P0]}
+
+assert_impl_all!(IN0: Read);
+assert_not_impl_any!(IN0: Write);
+// `modify` and `write_pin` need `Read` as well
+assert_impl_all!(OUTSET0: Write);
+assert_not_impl_any!(OUTSET0: Read);
+assert_impl_all!(OUT0: Read, Write);
+
+assert_impl_all!(CH_EEP0: ReadArray<20>);
+assert_not_impl_any!(CH_EEP0: WriteArray<20>);
+assert_impl_all!(TASKS_CHG_EN0: WriteArray<6>);
+assert_not_impl_any!(TASKS_CHG_EN0: ReadArray<6>);
+assert_impl_all!(PIN_CNF0: ReadArray<32>, WriteArray<32>);
+
+#[test]
+fn banks_are_at_their_offsets() {
+    assert_eq!(IN0::ADDR, 0x5000_0510);
+    assert_eq!(TASKS_CHG_EN0::ADDRS[1], 0x4001_F008);
+    assert_eq!(PIN_CNF0::ADDRS[31], 0x5000_077C);
+}
@@ -0,0 +1,232 @@
+//! Named resources of the BBC micro:bit v2.
+//!
+//! [`Board::take`] hands out the pins the micro:bit wires to something,
+//! already configured for it. Pins that are shared between the edge connector
+//! and an on-board part (the buttons, the LED columns) only show up under the
+//! part; release the handle to use them as a plain pad instead.
+use crate::button::{ButtonA, ButtonB, TouchLogo};
+use crate::clock::CLOCK;
+use crate::gpio::{Disconnected, Floating, Handle, HighDrive, Input, OpenDrain, Output, PushPull};
+use crate::gpiote::GPIOTE;
+use crate::rtc::{RTC0, RTC1, RTC2};
+use crate::spim::{SPIM2, SPIM3};
+use crate::timer::{TIMER0, TIMER1, TIMER2, TIMER3, TIMER4};
+use crate::twim::{TWIM0, TWIM1};
+use crate::uarte::{UARTE0, UARTE1};
+use crate::{
+    Level, Peripherals, P000, P001, P002, P003, P004, P005, P006, P008, P009, P010, P011, P012,
+    P013, P015, P016, P017, P019, P020, P021, P022, P024, P026, P028, P030, P031, P100, P102, P105,
+    P108,
+};
+
+/// The two buttons on the front
+pub struct Buttons {
+    pub a: ButtonA,
+    pub b: ButtonB,
+}
+
+/// Pins of the 5x5 LED matrix
+///
+/// An LED lights up when its row is driven high and its column low; all of
+/// them start off.
+pub struct DisplayPins {
+    pub row1: Handle<P021, Output<PushPull>>,
+    pub row2: Handle<P022, Output<PushPull>>,
+    pub row3: Handle<P015, Output<PushPull>>,
+    pub row4: Handle<P024, Output<PushPull>>,
+    pub row5: Handle<P019, Output<PushPull>>,
+    pub col1: Handle<P028, Output<PushPull>>,
+    pub col2: Handle<P011, Output<PushPull>>,
+    pub col3: Handle<P031, Output<PushPull>>,
+    pub col4: Handle<P105, Output<PushPull>>,
+    pub col5: Handle<P030, Output<PushPull>>,
+}
+
+/// The MEMS microphone
+pub struct Microphone {
+    /// Analog output of the microphone, AIN3
+    pub input: Handle<P005, Disconnected>,
+    /// Powers the microphone and its LED while high, starts low
+    pub run: Handle<P020, Output<HighDrive>>,
+}
+
+/// Pads of the edge connector that aren't wired to anything on the board
+pub struct EdgeConnector {
+    /// Large pad 0, P0.02
+    pub pad0: Handle<P002, Disconnected>,
+    /// Large pad 1, P0.03
+    pub pad1: Handle<P003, Disconnected>,
+    /// Large pad 2, P0.04
+    pub pad2: Handle<P004, Disconnected>,
+    /// Pin 8, P0.10
+    pub p8: Handle<P010, Disconnected>,
+    /// Pin 9, P0.09
+    pub p9: Handle<P009, Disconnected>,
+    /// Pin 12, P0.12
+    pub p12: Handle<P012, Disconnected>,
+    /// Pin 13, SPI SCK by convention, P0.17
+    pub p13: Handle<P017, Disconnected>,
+    /// Pin 14, SPI MISO by convention, P0.01
+    pub p14: Handle<P001, Disconnected>,
+    /// Pin 15, SPI MOSI by convention, P0.13
+    pub p15: Handle<P013, Disconnected>,
+    /// Pin 16, P1.02
+    pub p16: Handle<P102, Disconnected>,
+}
+
+/// Clock and data line of an I2C bus, both open-drain and released high
+pub struct I2cPins<SCL: crate::Pin, SDA: crate::Pin> {
+    pub scl: Handle<SCL, Output<OpenDrain>>,
+    pub sda: Handle<SDA, Output<OpenDrain>>,
+}
+
+/// UART to the interface chip, which shows up as a serial port over USB
+pub struct UartPins {
+    /// Idles high
+    pub tx: Handle<P006, Output<PushPull>>,
+    pub rx: Handle<P108, Input<Floating>>,
+}
+
+/// The resources of the micro:bit v2
+pub struct Board {
+    pub buttons: Buttons,
+    /// Touch sensitive logo, P1.04
+    pub logo: TouchLogo,
+    pub display: DisplayPins,
+    /// Speaker, P0.00
+    pub speaker: Handle<P000, Output<PushPull>>,
+    pub microphone: Microphone,
+    pub edge: EdgeConnector,
+    /// Bus to the accelerometer and magnetometer
+    pub i2c_internal: I2cPins<P008, P016>,
+    /// Bus on pins 19 and 20 of the edge connector
+    pub i2c_external: I2cPins<P026, P100>,
+    pub uart: UartPins,
+    pub clock: CLOCK,
+    pub gpiote: GPIOTE,
+    pub timer0: TIMER0,
+    /// Refreshes the display in the examples
+    pub timer1: TIMER1,
+    pub timer2: TIMER2,
+    pub timer3: TIMER3,
+    pub timer4: TIMER4,
+    /// Runs the internal I2C bus in the examples
+    pub twim0: TWIM0,
+    pub twim1: TWIM1,
+    pub spim2: SPIM2,
+    pub spim3: SPIM3,
+    /// Talks to the interface chip in the examples
+    pub uarte0: UARTE0,
+    pub uarte1: UARTE1,
+    pub rtc0: RTC0,
+    /// Keeps the time in the examples
+    pub rtc1: RTC1,
+    pub rtc2: RTC2,
+}
+impl Board {
+    /// Take the board, returning `None` if the peripherals have already been
+    /// taken
+    pub fn take() -> Option<Self> {
+        Peripherals::take().map(Board::new)
+    }
+    /// Configure the pins of `peripherals` for the parts they are wired to
+    pub fn new(peripherals: Peripherals) -> Self {
+        let p0 = peripherals.p0.split();
+        let p1 = peripherals.p1.split();
+        Board {
+            buttons: Buttons {
+                a: ButtonA::new(p0.p0_14),
+                b: ButtonB::new(p0.p0_23),
+            },
+            logo: TouchLogo::new(p1.p1_04),
+            display: DisplayPins {
+                row1: p0.p0_21.into_push_pull_output(Level::Low),
+                row2: p0.p0_22.into_push_pull_output(Level::Low),
+                row3: p0.p0_15.into_push_pull_output(Level::Low),
+                row4: p0.p0_24.into_push_pull_output(Level::Low),
+                row5: p0.p0_19.into_push_pull_output(Level::Low),
+                col1: p0.p0_28.into_push_pull_output(Level::High),
+                col2: p0.p0_11.into_push_pull_output(Level::High),
+                col3: p0.p0_31.into_push_pull_output(Level::High),
+                col4: p1.p1_05.into_push_pull_output(Level::High),
+                col5: p0.p0_30.into_push_pull_output(Level::High),
+            },
+            speaker: p0.p0_00.into_push_pull_output(Level::Low),
+            microphone: Microphone {
+                input: p0.p0_05,
+                run: p0.p0_20.into_high_drive_output(Level::Low),
+            },
+            edge: EdgeConnector {
+                pad0: p0.p0_02,
+                pad1: p0.p0_03,
+                pad2: p0.p0_04,
+                p8: p0.p0_10,
+                p9: p0.p0_09,
+                p12: p0.p0_12,
+                p13: p0.p0_17,
+                p14: p0.p0_01,
+                p15: p0.p0_13,
+                p16: p1.p1_02,
+            },
+            i2c_internal: I2cPins {
+                scl: p0.p0_08.into_open_drain_output(Level::High),
+                sda: p0.p0_16.into_open_drain_output(Level::High),
+            },
+            i2c_external: I2cPins {
+                scl: p0.p0_26.into_open_drain_output(Level::High),
+                sda: p1.p1_00.into_open_drain_output(Level::High),
+            },
+            uart: UartPins {
+                tx: p0.p0_06.into_push_pull_output(Level::High),
+                rx: p1.p1_08.into_floating_input(),
+            },
+            clock: peripherals.clock,
+            gpiote: peripherals.gpiote,
+            timer0: peripherals.timer0,
+            timer1: peripherals.timer1,
+            timer2: peripherals.timer2,
+            timer3: peripherals.timer3,
+            timer4: peripherals.timer4,
+            twim0: peripherals.twim0,
+            twim1: peripherals.twim1,
+            spim2: peripherals.spim2,
+            spim3: peripherals.spim3,
+            uarte0: peripherals.uarte0,
+            uarte1: peripherals.uarte1,
+            rtc0: peripherals.rtc0,
+            rtc1: peripherals.rtc1,
+            rtc2: peripherals.rtc2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::button::Button;
+    use crate::mem::sim;
+    use crate::{Addressable, DIR0, DIR1, OUT0, OUT1};
+
+    #[test]
+    fn board_configures_its_parts() {
+        sim::reset();
+        let board = Board::new(unsafe { Peripherals::steal() });
+        let rows = [21, 22, 15, 24, 19];
+        let cols = [28, 11, 31, 30];
+        for pin in rows.iter().chain(&cols) {
+            assert!(sim::peek(DIR0::ADDR) & 1 << pin != 0);
+        }
+        for pin in rows {
+            assert!(sim::peek(OUT0::ADDR) & 1 << pin == 0);
+        }
+        for pin in cols {
+            assert!(sim::peek(OUT0::ADDR) & 1 << pin != 0);
+        }
+        // COL4 and the external SDA line
+        assert_eq!(sim::peek(OUT1::ADDR) & sim::peek(DIR1::ADDR), 1 << 5 | 1);
+        assert_eq!(board.microphone.run.drive(), crate::Drive::H0H1);
+        assert_eq!(board.i2c_internal.sda.drive(), crate::Drive::S0D1);
+        assert!(!board.buttons.a.is_pressed());
+        assert!(board.i2c_external.sda.is_set_high());
+    }
+}
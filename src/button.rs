@@ -0,0 +1,344 @@
+//! Debounced buttons.
+//!
+//! A [`Button`] is sampled with [`Button::poll`], which feeds the raw level
+//! of its pin through a [`Debouncer`] and reports what happened as a
+//! [`ButtonEvent`]. All timings of a [`Debounce`] are counted in polls, so
+//! `poll` should be called at a steady rate, e.g. from a timer interrupt or a
+//! loop with a fixed delay. [`Button::wait_for_press`] and
+//! [`Button::wait_for_release`] do the latter themselves, pausing
+//! [`Debounce::poll_cycles`] between polls.
+//!
+//! ```ignore
+//! let mut board = Board::take().unwrap();
+//! loop {
+//!     match board.buttons.a.poll() {
+//!         Some(ButtonEvent::DoubleClick) => rprintln!("double click"),
+//!         Some(ButtonEvent::LongPress) => rprintln!("long press"),
+//!         _ => {}
+//!     }
+//!     cortex_m::asm::delay(64_000);
+//! }
+//! ```
+use crate::gpio::{Floating, Handle, Input, PullUp};
+use crate::{pause, Level, Pin, PinRegisters, P014, P023, P104};
+
+/// What a poll of a button saw
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// The button went down
+    Pressed,
+    /// The button went up
+    Released,
+    /// The button has been held for [`Debounce::long_press`] polls, reported
+    /// once per press
+    LongPress,
+    /// The button went down within [`Debounce::double_click`] polls of being
+    /// released, reported instead of [`Pressed`](Self::Pressed)
+    DoubleClick,
+}
+
+/// Timings of a [`Debouncer`], in polls
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Debounce {
+    /// Consecutive polls that must disagree with the current state before it
+    /// changes
+    pub samples: u8,
+    /// Polls the button must be held before a long press is reported
+    pub long_press: u16,
+    /// Most polls between a release and the next press for it to count as a
+    /// double click
+    pub double_click: u16,
+    /// CPU cycles to pause between polls in the `wait_for_*` methods
+    pub poll_cycles: u32,
+}
+/// Polls every millisecond at 64 MHz, 5 ms debounce, 500 ms long press and
+/// 250 ms between the clicks of a double click
+impl Default for Debounce {
+    fn default() -> Self {
+        Debounce {
+            samples: 5,
+            long_press: 500,
+            double_click: 250,
+            poll_cycles: 64_000,
+        }
+    }
+}
+
+/// Turns raw samples of a button into debounced [`ButtonEvent`]s
+#[derive(Clone, Debug)]
+pub struct Debouncer {
+    config: Debounce,
+    pressed: bool,
+    /// Consecutive samples disagreeing with `pressed`
+    changing: u8,
+    /// Polls since the press, while pressed
+    held: u16,
+    long_press_sent: bool,
+    /// Whether the current press is the second click of a double click
+    second_click: bool,
+    /// Polls since the release, while a double click can still happen
+    since_release: Option<u16>,
+}
+impl Debouncer {
+    /// Debouncer for a button that starts out released
+    pub const fn new(config: Debounce) -> Self {
+        Debouncer {
+            config,
+            pressed: false,
+            changing: 0,
+            held: 0,
+            long_press_sent: false,
+            second_click: false,
+            since_release: None,
+        }
+    }
+    pub fn config(&self) -> Debounce {
+        self.config
+    }
+    pub fn set_config(&mut self, config: Debounce) {
+        self.config = config
+    }
+    /// The debounced state
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+    /// Feed one raw sample, `true` meaning the button reads as pressed
+    pub fn update(&mut self, pressed: bool) -> Option<ButtonEvent> {
+        if pressed == self.pressed {
+            self.changing = 0;
+            return self.tick();
+        }
+        self.changing += 1;
+        if self.changing < self.config.samples {
+            return self.tick();
+        }
+        self.changing = 0;
+        self.pressed = pressed;
+        if pressed {
+            self.held = 0;
+            self.long_press_sent = false;
+            self.second_click = self.since_release.take().is_some();
+            if self.second_click {
+                Some(ButtonEvent::DoubleClick)
+            } else {
+                Some(ButtonEvent::Pressed)
+            }
+        } else {
+            // Neither a long press nor a third click starts a double click
+            if !self.long_press_sent && !self.second_click {
+                self.since_release = Some(0);
+            }
+            Some(ButtonEvent::Released)
+        }
+    }
+    /// Advance the timers while the debounced state stays as it is
+    fn tick(&mut self) -> Option<ButtonEvent> {
+        if self.pressed {
+            self.held = self.held.saturating_add(1);
+            if !self.long_press_sent && self.held >= self.config.long_press {
+                self.long_press_sent = true;
+                return Some(ButtonEvent::LongPress);
+            }
+        } else if let Some(polls) = self.since_release {
+            self.since_release = (polls < self.config.double_click).then_some(polls + 1);
+        }
+        None
+    }
+}
+
+/// A push button, or anything else that reads as pressed or released
+pub trait Button {
+    type Pin: Pin;
+    /// Level the pin reads while the button is pressed
+    const PRESSED: Level = Level::Low;
+
+    fn debouncer(&self) -> &Debouncer;
+    fn debouncer_mut(&mut self) -> &mut Debouncer;
+
+    /// Whether the pin reads as pressed right now, without debouncing
+    fn is_pressed_raw(&self) -> bool {
+        Self::Pin::read_in() == Self::PRESSED
+    }
+    /// Whether the button is pressed, as of the last poll
+    fn is_pressed(&self) -> bool {
+        self.debouncer().is_pressed()
+    }
+    /// Sample the button once
+    fn poll(&mut self) -> Option<ButtonEvent> {
+        let pressed = self.is_pressed_raw();
+        self.debouncer_mut().update(pressed)
+    }
+    fn set_debounce(&mut self, config: Debounce) {
+        self.debouncer_mut().set_config(config)
+    }
+    /// Poll until the button goes down, including as the second click of a
+    /// double click
+    fn wait_for_press(&mut self) {
+        while !matches!(
+            self.poll(),
+            Some(ButtonEvent::Pressed | ButtonEvent::DoubleClick)
+        ) {
+            pause(self.debouncer().config().poll_cycles)
+        }
+    }
+    /// Poll until the button goes up
+    fn wait_for_release(&mut self) {
+        while self.poll() != Some(ButtonEvent::Released) {
+            pause(self.debouncer().config().poll_cycles)
+        }
+    }
+}
+
+macro_rules! def_button {
+    ($(#[$doc:meta])* $name:ident, $pin:ty, $pull:ty, $into:ident) => {
+        $(#[$doc])*
+        pub struct $name {
+            pin: Handle<$pin, Input<$pull>>,
+            debouncer: Debouncer,
+        }
+        impl $name {
+            /// Take `pin`, configure it and debounce it with the default timings
+            pub fn new<MODE>(pin: Handle<$pin, MODE>) -> Self {
+                $name {
+                    pin: pin.$into(),
+                    debouncer: Debouncer::new(Debounce::default()),
+                }
+            }
+            /// Give the pin back, leaving its configuration as it is
+            pub fn release(self) -> Handle<$pin, Input<$pull>> {
+                self.pin
+            }
+        }
+        impl Button for $name {
+            type Pin = $pin;
+            fn debouncer(&self) -> &Debouncer {
+                &self.debouncer
+            }
+            fn debouncer_mut(&mut self) -> &mut Debouncer {
+                &mut self.debouncer
+            }
+        }
+    };
+}
+def_button!(
+    /// Button A on the front of the micro:bit, P0.14
+    ButtonA,
+    P014,
+    PullUp,
+    into_pull_up_input
+);
+def_button!(
+    /// Button B on the front of the micro:bit, P0.23
+    ButtonB,
+    P023,
+    PullUp,
+    into_pull_up_input
+);
+def_button!(
+    /// The touch sensitive logo, P1.04, which reads low while touched
+    ///
+    /// The pad is pulled up on the board through a large resistor that an
+    /// internal pull-up would swamp, so the pin is left floating.
+    TouchLogo,
+    P104,
+    Floating,
+    into_floating_input
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::sim;
+    use crate::{Addressable, Peripherals, IN0, IN1};
+
+    fn config() -> Debounce {
+        Debounce {
+            samples: 3,
+            long_press: 10,
+            double_click: 4,
+            poll_cycles: 0,
+        }
+    }
+    fn feed(debouncer: &mut Debouncer, pressed: bool, polls: usize) -> Vec<ButtonEvent> {
+        (0..polls)
+            .filter_map(|_| debouncer.update(pressed))
+            .collect()
+    }
+
+    #[test]
+    fn bounces_shorter_than_the_debounce_are_ignored() {
+        let mut debouncer = Debouncer::new(config());
+        assert!(feed(&mut debouncer, true, 2).is_empty());
+        assert!(feed(&mut debouncer, false, 1).is_empty());
+        assert!(feed(&mut debouncer, true, 2).is_empty());
+        assert_eq!(feed(&mut debouncer, true, 1), [ButtonEvent::Pressed]);
+        assert!(debouncer.is_pressed());
+        assert_eq!(feed(&mut debouncer, false, 3), [ButtonEvent::Released]);
+    }
+
+    #[test]
+    fn long_press_is_reported_once() {
+        let mut debouncer = Debouncer::new(config());
+        assert_eq!(
+            feed(&mut debouncer, true, 30),
+            [ButtonEvent::Pressed, ButtonEvent::LongPress]
+        );
+        assert_eq!(feed(&mut debouncer, false, 3), [ButtonEvent::Released]);
+        // A release after a long press doesn't start a double click
+        assert_eq!(feed(&mut debouncer, true, 3), [ButtonEvent::Pressed]);
+    }
+
+    #[test]
+    fn second_quick_click_is_a_double_click() {
+        let mut debouncer = Debouncer::new(config());
+        let mut events = feed(&mut debouncer, true, 3);
+        events.extend(feed(&mut debouncer, false, 3));
+        events.extend(feed(&mut debouncer, true, 3));
+        events.extend(feed(&mut debouncer, false, 3));
+        events.extend(feed(&mut debouncer, true, 3));
+        assert_eq!(
+            events,
+            [
+                ButtonEvent::Pressed,
+                ButtonEvent::Released,
+                ButtonEvent::DoubleClick,
+                ButtonEvent::Released,
+                ButtonEvent::Pressed,
+            ]
+        );
+        // Too long a gap makes two single clicks
+        events = feed(&mut debouncer, false, 10);
+        events.extend(feed(&mut debouncer, true, 3));
+        assert_eq!(events, [ButtonEvent::Released, ButtonEvent::Pressed]);
+    }
+
+    #[test]
+    fn buttons_read_low_as_pressed() {
+        sim::reset();
+        let pins = unsafe { Peripherals::steal() }.p0.split();
+        let mut button = ButtonA::new(pins.p0_14);
+        assert_eq!(sim::peek(0x5000_0700 + 14 * 4), 0b1100);
+        sim::poke(IN0::ADDR, !(1 << 14));
+        button.set_debounce(config());
+        button.wait_for_press();
+        assert!(button.is_pressed());
+        sim::poke(IN0::ADDR, 1 << 14);
+        button.wait_for_release();
+        assert!(!button.is_pressed());
+    }
+
+    #[test]
+    fn touch_logo_floats_and_reads_low_when_touched() {
+        sim::reset();
+        let pins = unsafe { Peripherals::steal() }.p1.split();
+        let mut logo = TouchLogo::new(pins.p1_04);
+        assert_eq!(sim::peek(0x5000_0A00 + 4 * 4), 0);
+        logo.set_debounce(config());
+        sim::poke(IN1::ADDR, !(1 << 4));
+        logo.wait_for_press();
+        assert!(logo.is_pressed());
+        sim::poke(IN1::ADDR, 1 << 4);
+        logo.wait_for_release();
+        assert!(!logo.is_pressed());
+    }
+}
@@ -0,0 +1,95 @@
+//! Clock control.
+//!
+//! Only the low frequency clock is managed here, since the high frequency
+//! one runs from the internal oscillator on demand. The RTCs count the low
+//! frequency clock, so it has to be started before any of them:
+//!
+//! ```ignore
+//! let mut board = Board::take().unwrap();
+//! board.clock.start_lfclk(LfclkSource::Rc, None).unwrap();
+//! let clock = Monotonic::new(board.rtc1);
+//! ```
+use crate::time::{Deadline, Duration};
+use crate::{Addressable, Error, Read, Register, Write};
+
+micro_macro::svd! { "svd/nrf52833.svd", CLOCK }
+
+/// Oscillator the 32.768 kHz low frequency clock runs from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LfclkSource {
+    /// Internal RC oscillator, the only one fitted on the micro:bit
+    Rc,
+    /// External 32.768 kHz crystal
+    Xtal,
+    /// Synthesized from the high frequency clock, which then keeps running
+    Synth,
+}
+
+impl CLOCK {
+    /// Start the low frequency clock from `source` and wait until it runs,
+    /// doing nothing if it already runs from `source`
+    ///
+    /// With a `timeout`, as measured by [`time::now`](crate::time::now), an
+    /// oscillator that doesn't start is given up on with
+    /// [`ErrorKind::Timeout`](crate::ErrorKind::Timeout), and the call fails
+    /// with [`ErrorKind::InvalidConfig`](crate::ErrorKind::InvalidConfig) if
+    /// no monotonic clock is running. The RTCs count this clock, though, so
+    /// a monotonic clock on one of them stands still while it is stopped and
+    /// the timeout only ends the wait if that clock is kept running.
+    pub fn start_lfclk(
+        &mut self,
+        source: LfclkSource,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        let stat = LFCLKSTAT0::read();
+        let current = match source {
+            LfclkSource::Rc => stat.src().is_rc(),
+            LfclkSource::Xtal => stat.src().is_xtal(),
+            LfclkSource::Synth => stat.src().is_synth(),
+        };
+        if stat.state().is_running() && current {
+            return Ok(());
+        }
+        let deadline = Deadline::after(timeout)?;
+        TASKS_LFCLKSTOP0::write(|w| w.tasks_lfclkstop().trigger());
+        LFCLKSRC0::write(|w| match source {
+            LfclkSource::Rc => w.src().rc(),
+            LfclkSource::Xtal => w.src().xtal(),
+            LfclkSource::Synth => w.src().synth(),
+        });
+        EVENTS_LFCLKSTARTED0::write(|w| w.events_lfclkstarted().notgenerated());
+        TASKS_LFCLKSTART0::write(|w| w.tasks_lfclkstart().trigger());
+        let started = deadline.wait(|| {
+            EVENTS_LFCLKSTARTED0::read()
+                .events_lfclkstarted()
+                .is_generated()
+        });
+        EVENTS_LFCLKSTARTED0::write(|w| w.events_lfclkstarted().notgenerated());
+        started
+    }
+    /// Whether the low frequency clock runs
+    pub fn is_lfclk_running(&self) -> bool {
+        LFCLKSTAT0::read().state().is_running()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::sim;
+    use crate::rtc::{Monotonic, RTC2};
+    use crate::ErrorKind;
+
+    #[test]
+    fn start_lfclk_gives_up_on_an_oscillator_that_never_starts() {
+        sim::reset();
+        let _clock = Monotonic::new(RTC2);
+        // Time stands still in the simulation, so only an empty wait expires
+        let error = CLOCK
+            .start_lfclk(LfclkSource::Xtal, Some(Duration::ZERO))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert_eq!(sim::peek(LFCLKSRC0::ADDR), 1);
+        assert_eq!(sim::peek(TASKS_LFCLKSTART0::ADDR), 1);
+    }
+}
@@ -0,0 +1,326 @@
+//! Driver for the 5x5 LED matrix.
+//!
+//! The LEDs are wired as five rows and five columns, an LED lighting up while
+//! its row is high and its column low, so only one row can show its own
+//! pattern at a time. [`Display`] lights the rows one after another from the
+//! interrupt of a hardware timer, fast enough for the eye to see the whole
+//! image. Each row is lit for [`ROW_TICKS`] microseconds; pixels that are not
+//! at full brightness are switched off early within that slot by a second
+//! compare channel.
+//!
+//! ```ignore
+//! static DISPLAY: Mutex<RefCell<Option<Display<TIMER1>>>> = Mutex::new(RefCell::new(None));
+//!
+//! let board = Board::take().unwrap();
+//! let mut display = Display::new(board.display, board.timer1);
+//! display.scroll("Hello");
+//! critical_section::with(|cs| DISPLAY.borrow_ref_mut(cs).replace(display));
+//! unsafe { NVIC::unmask(Interrupt::TIMER1) };
+//!
+//! #[interrupt]
+//! fn TIMER1() {
+//!     critical_section::with(|cs| {
+//!         if let Some(display) = DISPLAY.borrow_ref_mut(cs).as_mut() {
+//!             display.on_interrupt();
+//!         }
+//!     });
+//! }
+//! ```
+use crate::board::DisplayPins;
+use crate::graphics::{Animation, Image5x5, Scroller, MAX_BRIGHTNESS};
+use crate::timer::{
+    Instance, BITMODE, CC, EVENTS_COMPARE, INTENCLR, INTENSET, MODE, PRESCALER, SHORTS,
+    TASKS_CLEAR, TASKS_START, TASKS_STOP,
+};
+use crate::{PinMask, PortRegisters, P0, P1};
+
+/// Microseconds each row is lit for, giving a refresh rate of 200 Hz
+pub const ROW_TICKS: u32 = 1_000;
+/// Microseconds a pixel of each brightness level stays lit within its row,
+/// roughly doubling the perceived brightness every two levels
+const ON_TICKS: [u32; 10] = [0, 8, 16, 32, 60, 100, 170, 280, 500, ROW_TICKS];
+/// Compare value the counter never reaches within a row
+const NEVER: u32 = 0xFFFF;
+/// Whole images refreshed per second, the rate scrolling text and
+/// animations are ticked at
+pub const FRAME_RATE: u16 = (1_000_000 / (ROW_TICKS * 5)) as u16;
+/// Frames each column of scrolled text stays on screen by default, 120 ms
+pub const SCROLL_FRAMES: u16 = 24;
+
+/// P0 pins of the rows, top first
+const ROWS: [usize; 5] = [21, 22, 15, 24, 19];
+/// Pins of the columns, leftmost first, as port and pin number
+const COLS: [(usize, usize); 5] = [(0, 28), (0, 11), (0, 31), (1, 5), (0, 30)];
+const ALL_ROWS: PinMask = PinMask::from_pins(&ROWS);
+
+/// What the display is showing
+enum Content {
+    Still,
+    Scroll(Scroller<'static>),
+    Animation(Animation<'static>),
+}
+
+/// The LED matrix, refreshed from the interrupt of timer `T`
+pub struct Display<T: Instance> {
+    pins: DisplayPins,
+    timer: T,
+    image: Image5x5,
+    /// Row lit at the moment
+    row: usize,
+    /// Brightness level the next `COMPARE[1]` switches off
+    level: u8,
+    content: Content,
+}
+impl<T: Instance> Display<T> {
+    /// Take the display pins and `timer` and start refreshing a blank image
+    ///
+    /// Nothing shows until the interrupt of the timer is unmasked and calls
+    /// [`on_interrupt`](Self::on_interrupt).
+    pub fn new(pins: DisplayPins, timer: T) -> Self {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+        T::MODE::write(|w| w.mode().timer());
+        T::BITMODE::write(|w| w.bitmode()._16bit());
+        // 16 MHz / 2^4
+        T::PRESCALER::write(|w| w.prescaler().bits(4));
+        T::CC::write(0, |w| w.cc().bits(ROW_TICKS));
+        T::CC::write(1, |w| w.cc().bits(NEVER));
+        T::SHORTS::write(|w| w.compare0_clear().enabled());
+        T::INTENSET::write(|w| w.compare0().set_bit().compare1().set_bit());
+        T::TASKS_CLEAR::write(|w| w.tasks_clear().trigger());
+        T::TASKS_START::write(|w| w.tasks_start().trigger());
+        Display {
+            pins,
+            timer,
+            image: Image5x5::BLANK,
+            row: 0,
+            level: 0,
+            content: Content::Still,
+        }
+    }
+    /// Stop refreshing, switch all LEDs off and give back the pins and timer
+    pub fn free(self) -> (DisplayPins, T) {
+        T::INTENCLR::write(|w| w.compare0().set_bit().compare1().set_bit());
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+        Self::cols_off();
+        <P0 as PortRegisters>::set_low_masked(ALL_ROWS);
+        (self.pins, self.timer)
+    }
+    /// Show `image` until told otherwise, stopping any scrolling text or
+    /// animation
+    pub fn show(&mut self, image: &Image5x5) {
+        self.content = Content::Still;
+        self.image = *image;
+    }
+    /// Switch all LEDs off
+    pub fn clear(&mut self) {
+        self.show(&Image5x5::BLANK)
+    }
+    /// Scroll `text` across the display from right to left, once, at
+    /// [`SCROLL_FRAMES`] frames per column
+    pub fn scroll(&mut self, text: &'static str) {
+        self.scroll_at(text, SCROLL_FRAMES)
+    }
+    /// Scroll `text` across the display from right to left, once, moving one
+    /// column every `frames_per_column` frames
+    pub fn scroll_at(&mut self, text: &'static str, frames_per_column: u16) {
+        let scroller = Scroller::new(text, frames_per_column);
+        self.image = scroller.image();
+        self.content = Content::Scroll(scroller);
+    }
+    /// Play `animation`, ticking it once per frame
+    pub fn animate(&mut self, animation: Animation<'static>) {
+        self.image = animation.image();
+        self.content = Content::Animation(animation);
+    }
+    /// Whether text is still scrolling
+    pub fn is_scrolling(&self) -> bool {
+        matches!(self.content, Content::Scroll(_))
+    }
+    /// Whether an animation is still playing
+    pub fn is_animating(&self) -> bool {
+        matches!(self.content, Content::Animation(_))
+    }
+    /// The image being shown
+    pub fn image(&self) -> &Image5x5 {
+        &self.image
+    }
+    /// Advance the refresh, call this from the interrupt of the timer
+    pub fn on_interrupt(&mut self) {
+        if T::EVENTS_COMPARE::read(1).events_compare().is_generated() {
+            T::EVENTS_COMPARE::write(1, |w| w.events_compare().notgenerated());
+            self.dim();
+        }
+        if T::EVENTS_COMPARE::read(0).events_compare().is_generated() {
+            T::EVENTS_COMPARE::write(0, |w| w.events_compare().notgenerated());
+            self.next_row();
+        }
+    }
+
+    fn cols_off() {
+        let (p0, p1) = Self::cols(|_| true);
+        <P0 as PortRegisters>::set_high_masked(p0);
+        <P1 as PortRegisters>::set_high_masked(p1);
+    }
+    /// The columns for which `f` holds, as masks of P0 and P1
+    fn cols(f: impl Fn(usize) -> bool) -> (PinMask, PinMask) {
+        let mut masks = [PinMask::EMPTY; 2];
+        for (x, (port, pin)) in COLS.into_iter().enumerate() {
+            if f(x) {
+                masks[port] = masks[port].with(pin);
+            }
+        }
+        (masks[0], masks[1])
+    }
+    /// Schedule `COMPARE[1]` for the dimmest level above `level` in the row
+    fn schedule_dim(&mut self, level: u8) {
+        let row = self.image.row(self.row);
+        let next = row
+            .into_iter()
+            .filter(|b| *b > level && *b < MAX_BRIGHTNESS)
+            .min();
+        let ticks = match next {
+            Some(b) => {
+                self.level = b;
+                ON_TICKS[b as usize]
+            }
+            None => NEVER,
+        };
+        T::CC::write(1, |w| w.cc().bits(ticks));
+    }
+    fn dim(&mut self) {
+        let row = self.image.row(self.row);
+        let level = self.level;
+        let (p0, p1) = Self::cols(|x| row[x] <= level);
+        <P0 as PortRegisters>::set_high_masked(p0);
+        <P1 as PortRegisters>::set_high_masked(p1);
+        self.schedule_dim(level);
+    }
+    fn next_row(&mut self) {
+        Self::cols_off();
+        self.row = (self.row + 1) % 5;
+        if self.row == 0 {
+            self.next_frame();
+        }
+        <P0 as PortRegisters>::write_masked(ALL_ROWS, PinMask::pin(ROWS[self.row]));
+        let row = self.image.row(self.row);
+        let (p0, p1) = Self::cols(|x| row[x] > 0);
+        <P0 as PortRegisters>::set_low_masked(p0);
+        <P1 as PortRegisters>::set_low_masked(p1);
+        self.schedule_dim(0);
+    }
+    fn next_frame(&mut self) {
+        let (changed, finished, image) = match &mut self.content {
+            Content::Still => return,
+            Content::Scroll(scroller) => {
+                (scroller.tick(), scroller.is_finished(), scroller.image())
+            }
+            Content::Animation(animation) => {
+                (animation.tick(), animation.is_finished(), animation.image())
+            }
+        };
+        if changed || finished {
+            self.image = image;
+        }
+        if finished {
+            self.content = Content::Still;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::mem::sim;
+    use crate::timer::{EVENTS_COMPARE1, TIMER1};
+    use crate::{Peripherals, Read, RegisterArray, OUT0, OUT1};
+
+    const ROW3: u32 = 1 << 15;
+    const COL2: u32 = 1 << 11;
+    const COL4: u32 = 1 << 5;
+
+    fn fire(channel: usize) {
+        sim::poke(EVENTS_COMPARE1::ADDRS[channel], 1);
+    }
+    fn display() -> Display<TIMER1> {
+        sim::reset();
+        let board = Board::new(unsafe { Peripherals::steal() });
+        Display::new(board.display, board.timer1)
+    }
+
+    #[test]
+    fn timer_runs_at_one_mhz_with_row_period() {
+        let _display = display();
+        assert_eq!(sim::peek(0x4000_9510), 4);
+        assert_eq!(sim::peek(0x4000_9540), ROW_TICKS);
+        assert_eq!(sim::peek(0x4000_9200), 1);
+        assert_eq!(sim::peek(0x4000_9304), 0b11 << 16);
+        assert_eq!(sim::peek(0x4000_9000), 1);
+    }
+
+    #[test]
+    fn rows_are_lit_one_at_a_time() {
+        let mut display = display();
+        let mut image = Image5x5::BLANK;
+        image.set_pixel(1, 2, MAX_BRIGHTNESS);
+        image.set_pixel(3, 2, 4);
+        display.show(&image);
+        for _ in 0..2 {
+            fire(0);
+            display.on_interrupt();
+        }
+        assert_eq!(OUT0::read_raw() & ALL_ROWS.bits(), ROW3);
+        assert_eq!(OUT0::read_raw() & COL2, 0);
+        assert_eq!(OUT1::read_raw() & COL4, 0);
+        assert_eq!(sim::peek(0x4000_9544), ON_TICKS[4]);
+        // The dim pixel goes out early, the bright one stays lit
+        fire(1);
+        display.on_interrupt();
+        assert_eq!(OUT1::read_raw() & COL4, COL4);
+        assert_eq!(OUT0::read_raw() & COL2, 0);
+        assert_eq!(sim::peek(0x4000_9544), NEVER);
+        fire(0);
+        display.on_interrupt();
+        assert_eq!(OUT0::read_raw() & COL2, COL2);
+        assert_eq!(OUT0::read_raw() & ALL_ROWS.bits(), 1 << ROWS[3]);
+    }
+
+    #[test]
+    fn text_scrolls_in_from_the_right() {
+        let mut display = display();
+        display.scroll("I");
+        assert_eq!(display.image(), &Image5x5::BLANK);
+        let mut frames = Vec::new();
+        while display.is_scrolling() {
+            for _ in 0..5 {
+                fire(0);
+                display.on_interrupt();
+            }
+            if frames.last() != Some(display.image()) {
+                frames.push(*display.image());
+            }
+        }
+        // Enters one column at a time, then leaves the same way
+        assert_eq!(frames[0], Image5x5::BLANK);
+        assert_eq!(frames[1].row(0), [0, 0, 0, 0, 9]);
+        assert_eq!(frames[4].row(0), [0, 9, 9, 9, 0]);
+        assert_eq!(frames[5], Image5x5::char('I'));
+        assert_eq!(frames.last(), Some(&Image5x5::BLANK));
+    }
+
+    #[test]
+    fn animations_advance_once_per_frame() {
+        static FRAMES: [Image5x5; 2] = [Image5x5::YES, Image5x5::NO];
+        let mut display = display();
+        display.animate(Animation::new(&FRAMES, 2));
+        assert_eq!(display.image(), &Image5x5::YES);
+        for frame in 0..4 {
+            for _ in 0..5 {
+                fire(0);
+                display.on_interrupt();
+            }
+            assert_eq!(display.is_animating(), frame < 3);
+        }
+        assert_eq!(display.image(), &Image5x5::NO);
+    }
+}
@@ -0,0 +1,77 @@
+//! The error type shared by the register traits and the drivers.
+use core::fmt;
+
+/// What went wrong
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A pin or array index outside the valid range
+    BadIndex,
+    /// The pin is already used by another driver
+    PinInUse,
+    /// The requested configuration is not supported by the peripheral
+    InvalidConfig,
+    /// The peripheral did not finish in time
+    Timeout,
+    /// The addressed device did not acknowledge
+    BusNack,
+    /// Data arrived before the previous data was read
+    Overrun,
+    /// A frame arrived without its stop bit, or the line was held low
+    Framing,
+    /// A frame arrived with the wrong parity
+    Parity,
+}
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::BadIndex => "index out of bounds",
+            Self::PinInUse => "pin already in use",
+            Self::InvalidConfig => "invalid configuration",
+            Self::Timeout => "timed out",
+            Self::BusNack => "not acknowledged",
+            Self::Overrun => "overrun",
+            Self::Framing => "framing error",
+            Self::Parity => "parity error",
+        })
+    }
+}
+
+/// Error returned by fallible register accesses and driver operations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Error(ErrorKind);
+impl Error {
+    #[inline]
+    pub const fn new(kind: ErrorKind) -> Self {
+        Error(kind)
+    }
+    #[inline]
+    pub fn kind(&self) -> ErrorKind {
+        self.0
+    }
+}
+impl From<ErrorKind> for Error {
+    #[inline]
+    fn from(kind: ErrorKind) -> Self {
+        Error(kind)
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+impl core::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_displays_its_kind() {
+        let error = Error::from(ErrorKind::BusNack);
+        assert_eq!(error.kind(), ErrorKind::BusNack);
+        assert_eq!(error.to_string(), "not acknowledged");
+        let _: &dyn core::error::Error = &error;
+    }
+}
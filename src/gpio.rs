@@ -0,0 +1,298 @@
+//! Owned GPIO pin handles with typestates.
+//!
+//! A [`Handle`] wraps one of the `P0xx`/`P1xx` pins together with the mode it
+//! has been configured in. Changing mode consumes the handle and writes the
+//! new configuration to `PIN_CNF` and `DIRSET`/`DIRCLR`, so operations that
+//! make no sense for the current mode are simply not available:
+//!
+//! ```compile_fail,E0599
+//! use microrust::Peripherals;
+//!
+//! let pins = Peripherals::take().unwrap().p0.split();
+//! let mut button = pins.p0_14.into_pull_up_input();
+//! button.set_high();
+//! ```
+use core::marker::PhantomData;
+
+use crate::{pincnf, Drive, Level, Pin, PinRegisters, Read, Write, PINCNF};
+
+/// Input buffer disconnected, pin not driven; the reset state of every pin
+pub struct Disconnected;
+/// Input with the given pull configuration
+pub struct Input<PULL>(PhantomData<PULL>);
+/// Pull resistor to VDD
+pub struct PullUp;
+/// Pull resistor to GND
+pub struct PullDown;
+/// No pull resistor
+pub struct Floating;
+/// Output with the given drive configuration
+pub struct Output<DRIVE>(PhantomData<DRIVE>);
+/// Drives both high and low (`S0S1`)
+pub struct PushPull;
+/// Drives low, disconnects when high (`S0D1`)
+pub struct OpenDrain;
+/// Drives both high and low with high drive strength (`H0H1`)
+pub struct HighDrive;
+/// Drives low with high drive strength, disconnects when high (`H0D1`)
+pub struct OpenDrainHighDrive;
+
+/// An owned pin `P` configured as `MODE`
+pub struct Handle<P: Pin, MODE> {
+    pin: P,
+    _mode: PhantomData<MODE>,
+}
+impl<P: Pin> Handle<P, Disconnected> {
+    /// Take ownership of `pin`, putting it back into its reset configuration
+    pub fn new(pin: P) -> Self {
+        Handle::configure(pin, |w| w.dir().input().input().disconnect())
+    }
+}
+impl<P: Pin, MODE> Handle<P, MODE> {
+    fn configure<F>(pin: P, f: F) -> Self
+    where
+        F: FnOnce(&mut pincnf::W) -> &mut pincnf::W,
+    {
+        P::PINCNF::write(P::PIN_ID, f);
+        if P::PINCNF::read(P::PIN_ID).dir().is_output() {
+            P::make_output();
+        } else {
+            P::make_input();
+        }
+        Handle {
+            pin,
+            _mode: PhantomData,
+        }
+    }
+    fn set_out(level: Level) {
+        P::write_out(level)
+    }
+    /// Give the pin back, leaving its configuration as it is
+    pub fn release(self) -> P {
+        self.pin
+    }
+    /// Disconnect the pin
+    pub fn into_disconnected(self) -> Handle<P, Disconnected> {
+        Handle::configure(self.pin, |w| w.dir().input().input().disconnect())
+    }
+    /// Configure the pin as an input without pull resistor
+    pub fn into_floating_input(self) -> Handle<P, Input<Floating>> {
+        Handle::configure(self.pin, |w| w.dir().input().input().connect())
+    }
+    /// Configure the pin as an input pulled up to VDD
+    pub fn into_pull_up_input(self) -> Handle<P, Input<PullUp>> {
+        Handle::configure(self.pin, |w| w.dir().input().pull().pullup())
+    }
+    /// Configure the pin as an input pulled down to GND
+    pub fn into_pull_down_input(self) -> Handle<P, Input<PullDown>> {
+        Handle::configure(self.pin, |w| w.dir().input().pull().pulldown())
+    }
+    /// Configure the pin as a push-pull output, starting at `initial`
+    pub fn into_push_pull_output(self, initial: Level) -> Handle<P, Output<PushPull>> {
+        Self::set_out(initial);
+        Handle::configure(self.pin, |w| w.dir().output().input().disconnect())
+    }
+    /// Configure the pin as an open-drain output, starting at `initial`
+    ///
+    /// The input buffer stays connected so the level on the line can be read
+    /// back, as needed for e.g. I2C.
+    pub fn into_open_drain_output(self, initial: Level) -> Handle<P, Output<OpenDrain>> {
+        Self::set_out(initial);
+        Handle::configure(self.pin, |w| w.dir().output().drive().s0d1())
+    }
+    /// Configure the pin as a high drive push-pull output, starting at
+    /// `initial`
+    ///
+    /// High drive sources and sinks several milliamps, e.g. to drive an LED
+    /// directly.
+    pub fn into_high_drive_output(self, initial: Level) -> Handle<P, Output<HighDrive>> {
+        Self::set_out(initial);
+        Handle::configure(self.pin, |w| {
+            w.dir().output().input().disconnect().drive().h0h1()
+        })
+    }
+    /// Configure the pin as a high drive open-drain output, starting at
+    /// `initial`
+    ///
+    /// Like [`into_open_drain_output`](Self::into_open_drain_output), but
+    /// sinks with high drive strength.
+    pub fn into_open_drain_high_drive_output(
+        self,
+        initial: Level,
+    ) -> Handle<P, Output<OpenDrainHighDrive>> {
+        Self::set_out(initial);
+        Handle::configure(self.pin, |w| w.dir().output().drive().h0d1())
+    }
+}
+impl<P: Pin, PULL> Handle<P, Input<PULL>> {
+    /// Whether the pin reads high
+    pub fn is_high(&self) -> bool {
+        P::IN::read_pins().contains(P::PIN_ID)
+    }
+    /// Whether the pin reads low
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+    /// Raise `DETECT` and set the latch of the pin while it reads `level`
+    ///
+    /// This is also what wakes the chip from System OFF.
+    pub fn sense(&mut self, level: Level) {
+        match level {
+            Level::High => P::sense_high(),
+            Level::Low => P::sense_low(),
+        }
+    }
+    /// Stop sensing the pin
+    pub fn sense_disable(&mut self) {
+        P::sense_disable()
+    }
+    /// Whether the pin has met its sense level since its latch was cleared
+    pub fn is_latched(&self) -> bool {
+        P::LATCH::read_pins().contains(P::PIN_ID)
+    }
+    /// Clear the latch of the pin
+    ///
+    /// A latch that is cleared while the pin is still at the sense level is
+    /// set again right away.
+    pub fn clear_latch(&mut self) {
+        P::LATCH::write_pins(P::PIN_MASK)
+    }
+}
+impl<P: Pin, DRIVE> Handle<P, Output<DRIVE>> {
+    /// Drive the pin high
+    pub fn set_high(&mut self) {
+        Self::set_out(Level::High)
+    }
+    /// Drive the pin low
+    pub fn set_low(&mut self) {
+        Self::set_out(Level::Low)
+    }
+    /// Drive the pin to `level`
+    pub fn set_state(&mut self, level: Level) {
+        Self::set_out(level)
+    }
+    /// Flip the level the pin is driven to
+    pub fn toggle(&mut self) {
+        P::toggle()
+    }
+    /// Whether the pin is set to drive high
+    pub fn is_set_high(&self) -> bool {
+        P::OUT::read_pins().contains(P::PIN_ID)
+    }
+    /// Whether the pin is set to drive low
+    pub fn is_set_low(&self) -> bool {
+        !self.is_set_high()
+    }
+    /// Switch the pin to any of the eight drive configurations, keeping the
+    /// rest of its `PIN_CNF`
+    ///
+    /// The handle keeps the output type it was made with, which only decides
+    /// the methods it offers.
+    pub fn set_drive(&mut self, drive: Drive) {
+        P::set_drive(drive)
+    }
+    /// The drive configuration the pin is set to
+    pub fn drive(&self) -> Drive {
+        P::drive()
+    }
+}
+impl<P: Pin> Handle<P, Output<OpenDrain>> {
+    /// Whether the line reads high, i.e. no device is pulling it low
+    pub fn is_high(&self) -> bool {
+        P::IN::read_pins().contains(P::PIN_ID)
+    }
+    /// Whether the line reads low
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+impl<P: Pin> Handle<P, Output<OpenDrainHighDrive>> {
+    /// Whether the line reads high, i.e. no device is pulling it low
+    pub fn is_high(&self) -> bool {
+        P::IN::read_pins().contains(P::PIN_ID)
+    }
+    /// Whether the line reads low
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::sim;
+    use crate::{Addressable, DIR0, DIR1, IN0, OUT0, OUT1, P014, P105};
+
+    const DIR_OUTPUT: u32 = 0b0001;
+    const INPUT_DISCONNECT: u32 = 0b0010;
+    const PULL_DOWN: u32 = 0b0100;
+    const PULL_UP: u32 = 0b1100;
+    const DRIVE_S0D1: u32 = 0b110 << 8;
+    const DRIVE_H0H1: u32 = 0b011 << 8;
+    const DRIVE_H0D1: u32 = 0b111 << 8;
+    const P014_CNF: usize = 0x5000_0700 + 14 * 4;
+    const P105_CNF: usize = 0x5000_0A00 + 5 * 4;
+
+    #[test]
+    fn input_transitions_write_pincnf_and_dirclr() {
+        sim::reset();
+        sim::poke(DIR0::ADDR, 1 << 14);
+        let pin = Handle::new(P014).into_pull_up_input();
+        assert_eq!(sim::peek(P014_CNF), PULL_UP);
+        assert_eq!(sim::peek(DIR0::ADDR), 0);
+        let pin = pin.into_pull_down_input();
+        assert_eq!(sim::peek(P014_CNF), PULL_DOWN);
+        pin.into_floating_input();
+        assert_eq!(sim::peek(P014_CNF), 0);
+    }
+
+    #[test]
+    fn input_reads_its_own_bit() {
+        sim::reset();
+        let pin = Handle::new(P014).into_floating_input();
+        sim::poke(IN0::ADDR, !(1 << 14));
+        assert!(pin.is_low());
+        sim::poke(IN0::ADDR, 1 << 14);
+        assert!(pin.is_high());
+    }
+
+    #[test]
+    fn output_sets_level_before_direction() {
+        sim::reset();
+        let mut pin = Handle::new(P105).into_push_pull_output(Level::High);
+        assert_eq!(
+            sim::writes()[2..],
+            [
+                (0x5000_0808, 1 << 5),
+                (P105_CNF, DIR_OUTPUT | INPUT_DISCONNECT),
+                (0x5000_0818, 1 << 5),
+            ]
+        );
+        assert_eq!(sim::peek(DIR1::ADDR), 1 << 5);
+        assert!(pin.is_set_high());
+        pin.set_low();
+        assert_eq!(sim::peek(OUT1::ADDR), 0);
+    }
+
+    #[test]
+    fn open_drain_output_uses_s0d1() {
+        sim::reset();
+        Handle::new(P105).into_open_drain_output(Level::Low);
+        assert_eq!(sim::peek(P105_CNF), DIR_OUTPUT | DRIVE_S0D1);
+    }
+
+    #[test]
+    fn high_drive_outputs_use_h0_drives() {
+        sim::reset();
+        let pin = Handle::new(P014).into_high_drive_output(Level::Low);
+        assert_eq!(
+            sim::peek(P014_CNF),
+            DIR_OUTPUT | INPUT_DISCONNECT | DRIVE_H0H1
+        );
+        assert_eq!(pin.drive(), Drive::H0H1);
+        let pin = pin.into_open_drain_high_drive_output(Level::High);
+        assert_eq!(sim::peek(P014_CNF), DIR_OUTPUT | DRIVE_H0D1);
+        assert_eq!(pin.drive(), Drive::H0D1);
+        assert_eq!(sim::peek(OUT0::ADDR), 1 << 14);
+    }
+}
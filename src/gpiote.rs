@@ -0,0 +1,326 @@
+//! GPIO tasks and events.
+//!
+//! The GPIOTE peripheral watches pins for level changes and drives pins from
+//! tasks. It has eight channels, each tied to one pin in either event mode,
+//! where an edge on an input sets `EVENTS_IN`, or task mode, where triggering
+//! `TASKS_OUT`/`TASKS_SET`/`TASKS_CLR` drives an output. The `PORT` event
+//! fires on the `DETECT` signal of the GPIO ports instead, so it can watch any
+//! number of pins that have `SENSE` configured.
+//!
+//! Channels and the port event can call a handler when they fire. Handlers
+//! are run by [`on_interrupt`], which is called from the `GPIOTE` interrupt:
+//!
+//! ```ignore
+//! use microrust::gpiote::{self, Edge};
+//! use microrust::{interrupt, Interrupt, Peripherals};
+//!
+//! let peripherals = Peripherals::take().unwrap();
+//! let pins = peripherals.p0.split();
+//! let mut gpiote = peripherals.gpiote.split();
+//! let button = pins.p0_14.into_pull_up_input();
+//! button.on_edge(&mut gpiote.ch0, Edge::Falling, || rprintln!("pressed"));
+//! unsafe { cortex_m::peripheral::NVIC::unmask(Interrupt::GPIOTE) };
+//!
+//! #[interrupt]
+//! fn GPIOTE() {
+//!     gpiote::on_interrupt();
+//! }
+//! ```
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::gpio::{Handle, Input, Output};
+use crate::{Addressable, Level, Pin, Read, ReadArray, Register, RegisterArray, Write, WriteArray};
+
+micro_macro::svd! { "svd/nrf52833.svd", GPIOTE }
+
+/// Number of channels
+pub const CHANNELS: usize = 8;
+/// Bit of the `PORT` event in `INTENSET`/`INTENCLR`
+const PORT_BIT: u32 = 1 << 31;
+
+type Handlers = [Cell<Option<fn()>>; CHANNELS + 1];
+/// Handlers of the channels, followed by the one of the port event
+static HANDLERS: Mutex<Handlers> = Mutex::new([const { Cell::new(None) }; CHANNELS + 1]);
+
+fn set_handler(index: usize, handler: Option<fn()>) {
+    critical_section::with(|cs| HANDLERS.borrow(cs)[index].set(handler))
+}
+fn handler(index: usize) -> Option<fn()> {
+    critical_section::with(|cs| HANDLERS.borrow(cs)[index].get())
+}
+
+/// Run the handlers of the channels and port event that have fired, clearing
+/// their events
+///
+/// Call this from the `GPIOTE` interrupt handler.
+pub fn on_interrupt() {
+    let enabled = INTENSET0::read_raw();
+    for index in 0..CHANNELS {
+        if enabled & (1 << index) != 0 && EVENTS_IN0::read(index).events_in().is_generated() {
+            EVENTS_IN0::write(index, |w| w.events_in().notgenerated());
+            if let Some(handler) = handler(index) {
+                handler()
+            }
+        }
+    }
+    if enabled & PORT_BIT != 0 && EVENTS_PORT0::read().events_port().is_generated() {
+        EVENTS_PORT0::write(|w| w.events_port().notgenerated());
+        if let Some(handler) = handler(CHANNELS) {
+            handler()
+        }
+    }
+}
+
+/// Edge of an input that sets a channel's event, or what a channel's `OUT`
+/// task does to its output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Low to high, or drive high
+    Rising,
+    /// High to low, or drive low
+    Falling,
+    /// Either way, or flip the output
+    Toggle,
+}
+impl From<Edge> for config::POLARITY_A {
+    #[inline]
+    fn from(edge: Edge) -> Self {
+        match edge {
+            Edge::Rising => config::POLARITY_A::Lotohi,
+            Edge::Falling => config::POLARITY_A::Hitolo,
+            Edge::Toggle => config::POLARITY_A::Toggle,
+        }
+    }
+}
+
+/// Channel `N` of the GPIOTE, from [`GPIOTE::split`]
+pub struct Channel<const N: usize> {
+    _private: (),
+}
+impl<const N: usize> Channel<N> {
+    /// Set the event of the channel on every `edge` of `pin`
+    pub fn input<P: Pin, PULL>(&mut self, _pin: &Handle<P, Input<PULL>>, edge: Edge) {
+        CONFIG0::write(N, |w| {
+            w.mode()
+                .event()
+                .psel()
+                .bits(P::PIN_ID as u32)
+                .port()
+                .bits(P::PORT_ID as u32)
+                .polarity()
+                .variant(edge.into())
+        });
+    }
+    /// Let the tasks of the channel drive `pin`, starting at `initial`
+    ///
+    /// `out` decides what the `OUT` task does. While the channel is in task
+    /// mode the pin no longer follows `OUT` of its port.
+    pub fn output<P: Pin, DRIVE>(
+        &mut self,
+        _pin: &Handle<P, Output<DRIVE>>,
+        out: Edge,
+        initial: Level,
+    ) {
+        CONFIG0::write(N, |w| {
+            let w = w
+                .mode()
+                .task()
+                .psel()
+                .bits(P::PIN_ID as u32)
+                .port()
+                .bits(P::PORT_ID as u32)
+                .polarity()
+                .variant(out.into())
+                .outinit();
+            match initial {
+                Level::High => w.high(),
+                Level::Low => w.low(),
+            }
+        });
+    }
+    /// Disconnect the channel from its pin, handing the pin back to its port
+    pub fn disable(&mut self) {
+        self.unlisten();
+        CONFIG0::write(N, |w| w.mode().disabled());
+    }
+    /// Trigger the `OUT` task
+    pub fn out(&self) {
+        TASKS_OUT0::write(N, |w| w.tasks_out().trigger());
+    }
+    /// Trigger the `SET` task, driving the pin high
+    pub fn set(&self) {
+        TASKS_SET0::write(N, |w| w.tasks_set().trigger());
+    }
+    /// Trigger the `CLR` task, driving the pin low
+    pub fn clear(&self) {
+        TASKS_CLR0::write(N, |w| w.tasks_clr().trigger());
+    }
+    /// Whether the event of the channel has fired since it was last reset
+    pub fn is_triggered(&self) -> bool {
+        EVENTS_IN0::read(N).events_in().is_generated()
+    }
+    /// Clear the event of the channel
+    pub fn reset(&self) {
+        EVENTS_IN0::write(N, |w| w.events_in().notgenerated());
+    }
+    /// Call `handler` from [`on_interrupt`] whenever the event fires
+    pub fn listen(&mut self, handler: fn()) {
+        set_handler(N, Some(handler));
+        self.reset();
+        INTENSET0::write(|w| w.bits(1 << N));
+    }
+    /// Stop the event from raising the interrupt and drop its handler
+    pub fn unlisten(&mut self) {
+        INTENCLR0::write(|w| w.bits(1 << N));
+        set_handler(N, None);
+    }
+}
+
+/// The `PORT` event of the GPIOTE, from [`GPIOTE::split`]
+pub struct PortEvent {
+    _private: (),
+}
+impl PortEvent {
+    /// Whether the event has fired since it was last reset
+    pub fn is_triggered(&self) -> bool {
+        EVENTS_PORT0::read().events_port().is_generated()
+    }
+    /// Clear the event
+    pub fn reset(&self) {
+        EVENTS_PORT0::write(|w| w.events_port().notgenerated());
+    }
+    /// Call `handler` from [`on_interrupt`] whenever the event fires
+    pub fn listen(&mut self, handler: fn()) {
+        set_handler(CHANNELS, Some(handler));
+        self.reset();
+        INTENSET0::write(|w| w.bits(PORT_BIT));
+    }
+    /// Stop the event from raising the interrupt and drop its handler
+    pub fn unlisten(&mut self) {
+        INTENCLR0::write(|w| w.bits(PORT_BIT));
+        set_handler(CHANNELS, None);
+    }
+}
+
+/// Channels and port event of the GPIOTE, from [`GPIOTE::split`]
+pub struct Parts {
+    pub ch0: Channel<0>,
+    pub ch1: Channel<1>,
+    pub ch2: Channel<2>,
+    pub ch3: Channel<3>,
+    pub ch4: Channel<4>,
+    pub ch5: Channel<5>,
+    pub ch6: Channel<6>,
+    pub ch7: Channel<7>,
+    pub port: PortEvent,
+}
+impl GPIOTE {
+    /// Split the peripheral into its channels and port event
+    pub fn split(self) -> Parts {
+        Parts {
+            ch0: Channel { _private: () },
+            ch1: Channel { _private: () },
+            ch2: Channel { _private: () },
+            ch3: Channel { _private: () },
+            ch4: Channel { _private: () },
+            ch5: Channel { _private: () },
+            ch6: Channel { _private: () },
+            ch7: Channel { _private: () },
+            port: PortEvent { _private: () },
+        }
+    }
+}
+
+impl<P: Pin, PULL> Handle<P, Input<PULL>> {
+    /// Call `handler` from [`on_interrupt`] on every `edge` of the pin,
+    /// watched through `channel`
+    pub fn on_edge<const N: usize>(&self, channel: &mut Channel<N>, edge: Edge, handler: fn()) {
+        channel.input(self, edge);
+        channel.listen(handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::mem::sim;
+    use crate::{P014, P105};
+
+    const CONFIG_EVENT: u32 = 1;
+    const CONFIG_TASK: u32 = 3;
+
+    #[test]
+    fn input_channel_selects_pin_and_edge() {
+        sim::reset();
+        let pin = Handle::new(P105).into_pull_up_input();
+        let mut parts = GPIOTE.split();
+        parts.ch2.input(&pin, Edge::Falling);
+        assert_eq!(
+            sim::peek(CONFIG0::ADDRS[2]),
+            CONFIG_EVENT | 5 << 8 | 1 << 13 | 2 << 16
+        );
+        assert_eq!(CONFIG0::ADDRS[2], 0x4000_6518);
+    }
+
+    #[test]
+    fn output_channel_drives_through_tasks() {
+        sim::reset();
+        let pin = Handle::new(P014).into_push_pull_output(Level::Low);
+        let mut parts = GPIOTE.split();
+        parts.ch1.output(&pin, Edge::Toggle, Level::High);
+        assert_eq!(
+            sim::peek(CONFIG0::ADDRS[1]),
+            CONFIG_TASK | 14 << 8 | 3 << 16 | 1 << 20
+        );
+        parts.ch1.set();
+        parts.ch1.out();
+        assert_eq!(sim::peek(TASKS_SET0::ADDRS[1]), 1);
+        assert_eq!(sim::peek(TASKS_OUT0::ADDRS[1]), 1);
+        parts.ch1.disable();
+        assert_eq!(sim::peek(CONFIG0::ADDRS[1]) & 0b11, 0);
+    }
+
+    thread_local! {
+        static CALLS: Cell<u32> = const { Cell::new(0) };
+    }
+    fn count() {
+        CALLS.with(|calls| calls.set(calls.get() + 1));
+    }
+
+    #[test]
+    fn interrupt_runs_handlers_of_fired_events() {
+        sim::reset();
+        let pin = Handle::new(P014).into_pull_up_input();
+        let mut parts = GPIOTE.split();
+        pin.on_edge(&mut parts.ch3, Edge::Rising, count);
+        parts.port.listen(count);
+        let enables = |register| {
+            sim::writes()
+                .into_iter()
+                .filter(|(addr, _)| *addr == register)
+                .map(|(_, bits)| bits)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(enables(INTENSET0::ADDR), [1 << 3, PORT_BIT]);
+        // INTENSET reads back the enabled interrupts, but the simulated
+        // memory only keeps the last write, so enable both at once
+        sim::poke(INTENSET0::ADDR, 1 << 3 | PORT_BIT);
+        sim::poke(EVENTS_IN0::ADDRS[3], 1);
+        sim::poke(EVENTS_IN0::ADDRS[4], 1);
+        on_interrupt();
+        assert_eq!(CALLS.with(Cell::get), 1);
+        assert!(!parts.ch3.is_triggered());
+        assert_eq!(sim::peek(EVENTS_IN0::ADDRS[4]), 1);
+        sim::poke(EVENTS_PORT0::ADDR, 1);
+        on_interrupt();
+        assert_eq!(CALLS.with(Cell::get), 2);
+        assert!(!parts.port.is_triggered());
+        parts.ch3.unlisten();
+        parts.port.unlisten();
+        assert_eq!(enables(INTENCLR0::ADDR), [1 << 3, PORT_BIT]);
+    }
+}
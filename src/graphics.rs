@@ -0,0 +1,17 @@
+//! Pictures and text for the LED matrix.
+//!
+//! Everything here is plain data without any hardware behind it: an
+//! [`Image5x5`] holds one brightness level per pixel, a [`Scroller`] turns
+//! text into the images of it moving across the screen and an [`Animation`]
+//! steps through a sequence of images. Scrollers and animations advance one
+//! tick at a time, leaving it to whoever shows them how long a tick lasts;
+//! [`Display`](crate::display::Display) ticks them once per refresh.
+mod animation;
+mod font;
+mod image;
+mod scroll;
+
+pub use animation::Animation;
+pub use font::{glyph, glyph_column};
+pub use image::{Image5x5, MAX_BRIGHTNESS};
+pub use scroll::Scroller;
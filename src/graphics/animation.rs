@@ -0,0 +1,123 @@
+//! Sequences of images.
+use super::Image5x5;
+
+/// Frames shown one after another, each for the same number of ticks
+///
+/// A looping animation starts over after its last frame, one that doesn't
+/// finishes and keeps showing its last frame.
+#[derive(Clone, Debug)]
+pub struct Animation<'a> {
+    frames: &'a [Image5x5],
+    ticks_per_frame: u16,
+    looping: bool,
+    /// Frame on screen
+    frame: usize,
+    ticks: u16,
+}
+impl<'a> Animation<'a> {
+    /// Play `frames` once, showing each for `ticks_per_frame` ticks
+    pub const fn new(frames: &'a [Image5x5], ticks_per_frame: u16) -> Self {
+        Animation {
+            frames,
+            ticks_per_frame,
+            looping: false,
+            frame: 0,
+            ticks: 0,
+        }
+    }
+    /// The animation, starting over after its last frame
+    pub const fn looping(self) -> Self {
+        Animation {
+            looping: true,
+            ..self
+        }
+    }
+    /// Ticks each frame lasts
+    pub fn speed(&self) -> u16 {
+        self.ticks_per_frame
+    }
+    /// Change the ticks each frame lasts, from the next frame on
+    pub fn set_speed(&mut self, ticks_per_frame: u16) {
+        self.ticks_per_frame = ticks_per_frame
+    }
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+    /// Whether a one-off animation has shown all of its frames, never true
+    /// for a looping one that has frames
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.frames.len()
+    }
+    /// Start over from the first frame
+    pub fn reset(&mut self) {
+        self.frame = 0;
+        self.ticks = 0;
+    }
+    /// Advance one tick, returning whether the image changed
+    pub fn tick(&mut self) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        self.ticks += 1;
+        if self.ticks < self.ticks_per_frame {
+            return false;
+        }
+        self.ticks = 0;
+        let before = self.image();
+        self.frame += 1;
+        if self.looping && self.is_finished() {
+            self.frame = 0;
+        }
+        self.image() != before
+    }
+    /// The frame on screen, the last one once finished and blank without any
+    /// frames
+    pub fn image(&self) -> Image5x5 {
+        match self.frames.get(self.frame) {
+            Some(image) => *image,
+            None => self.frames.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAMES: [Image5x5; 3] = [Image5x5::HEART, Image5x5::HAPPY, Image5x5::SAD];
+
+    fn play(animation: &mut Animation, ticks: usize) -> Vec<Image5x5> {
+        let mut images = Vec::new();
+        for _ in 0..ticks {
+            if animation.tick() {
+                images.push(animation.image());
+            }
+        }
+        images
+    }
+
+    #[test]
+    fn frames_play_once() {
+        let mut animation = Animation::new(&FRAMES, 2);
+        assert_eq!(animation.image(), Image5x5::HEART);
+        assert_eq!(play(&mut animation, 10), [Image5x5::HAPPY, Image5x5::SAD]);
+        assert!(animation.is_finished());
+        assert_eq!(animation.image(), Image5x5::SAD);
+        assert!(Animation::new(&[], 1).is_finished());
+    }
+
+    #[test]
+    fn looping_frames_start_over() {
+        let mut animation = Animation::new(&FRAMES, 1).looping();
+        assert_eq!(
+            play(&mut animation, 4),
+            [
+                Image5x5::HAPPY,
+                Image5x5::SAD,
+                Image5x5::HEART,
+                Image5x5::HAPPY
+            ]
+        );
+        assert!(!animation.is_finished());
+    }
+}
@@ -0,0 +1,245 @@
+//! 5x5 font covering printable ASCII.
+//!
+//! Each glyph is five rows of five bits, the most significant of the five
+//! being the leftmost column.
+
+/// Glyphs of `' '` to `'~'`
+const GLYPHS: [[u8; 5]; 95] = [
+    // ' '
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+    // '!'
+    [0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+    // '"'
+    [0b01010, 0b01010, 0b00000, 0b00000, 0b00000],
+    // '#'
+    [0b01010, 0b11111, 0b01010, 0b11111, 0b01010],
+    // '$'
+    [0b01111, 0b10100, 0b01110, 0b00101, 0b11110],
+    // '%'
+    [0b11001, 0b11010, 0b00100, 0b01011, 0b10011],
+    // '&'
+    [0b01100, 0b10010, 0b01100, 0b10010, 0b01101],
+    // '\''
+    [0b00100, 0b00100, 0b00000, 0b00000, 0b00000],
+    // '('
+    [0b00010, 0b00100, 0b00100, 0b00100, 0b00010],
+    // ')'
+    [0b01000, 0b00100, 0b00100, 0b00100, 0b01000],
+    // '*'
+    [0b00000, 0b01010, 0b00100, 0b01010, 0b00000],
+    // '+'
+    [0b00000, 0b00100, 0b01110, 0b00100, 0b00000],
+    // ','
+    [0b00000, 0b00000, 0b00000, 0b00100, 0b01000],
+    // '-'
+    [0b00000, 0b00000, 0b01110, 0b00000, 0b00000],
+    // '.'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00100],
+    // '/'
+    [0b00001, 0b00010, 0b00100, 0b01000, 0b10000],
+    // '0'
+    [0b01100, 0b10010, 0b10010, 0b10010, 0b01100],
+    // '1'
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b01110],
+    // '2'
+    [0b11100, 0b00010, 0b01100, 0b10000, 0b11110],
+    // '3'
+    [0b11110, 0b00010, 0b00100, 0b10010, 0b01100],
+    // '4'
+    [0b00110, 0b01010, 0b10010, 0b11111, 0b00010],
+    // '5'
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b11110],
+    // '6'
+    [0b00010, 0b00100, 0b01110, 0b10001, 0b01110],
+    // '7'
+    [0b11111, 0b00010, 0b00100, 0b01000, 0b10000],
+    // '8'
+    [0b01110, 0b10001, 0b01110, 0b10001, 0b01110],
+    // '9'
+    [0b01110, 0b10001, 0b01110, 0b00100, 0b01000],
+    // ':'
+    [0b00000, 0b00100, 0b00000, 0b00100, 0b00000],
+    // ';'
+    [0b00000, 0b00100, 0b00000, 0b00100, 0b01000],
+    // '<'
+    [0b00010, 0b00100, 0b01000, 0b00100, 0b00010],
+    // '='
+    [0b00000, 0b01110, 0b00000, 0b01110, 0b00000],
+    // '>'
+    [0b01000, 0b00100, 0b00010, 0b00100, 0b01000],
+    // '?'
+    [0b01110, 0b00001, 0b00110, 0b00000, 0b00100],
+    // '@'
+    [0b01110, 0b10001, 0b10111, 0b10101, 0b10110],
+    // 'A'
+    [0b01100, 0b10010, 0b11110, 0b10010, 0b10010],
+    // 'B'
+    [0b11100, 0b10010, 0b11100, 0b10010, 0b11100],
+    // 'C'
+    [0b01110, 0b10000, 0b10000, 0b10000, 0b01110],
+    // 'D'
+    [0b11100, 0b10010, 0b10010, 0b10010, 0b11100],
+    // 'E'
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b11110],
+    // 'F'
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b10000],
+    // 'G'
+    [0b01110, 0b10000, 0b10110, 0b10010, 0b01100],
+    // 'H'
+    [0b10010, 0b10010, 0b11110, 0b10010, 0b10010],
+    // 'I'
+    [0b11100, 0b01000, 0b01000, 0b01000, 0b11100],
+    // 'J'
+    [0b11111, 0b00100, 0b00100, 0b10100, 0b01000],
+    // 'K'
+    [0b10010, 0b10100, 0b11000, 0b10100, 0b10010],
+    // 'L'
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b11110],
+    // 'M'
+    [0b10001, 0b11011, 0b10101, 0b10001, 0b10001],
+    // 'N'
+    [0b10001, 0b11001, 0b10101, 0b10011, 0b10001],
+    // 'O'
+    [0b01100, 0b10010, 0b10010, 0b10010, 0b01100],
+    // 'P'
+    [0b11100, 0b10010, 0b11100, 0b10000, 0b10000],
+    // 'Q'
+    [0b01100, 0b10010, 0b10010, 0b01100, 0b00010],
+    // 'R'
+    [0b11100, 0b10010, 0b11100, 0b10100, 0b10010],
+    // 'S'
+    [0b01110, 0b10000, 0b01100, 0b00010, 0b11100],
+    // 'T'
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100],
+    // 'U'
+    [0b10010, 0b10010, 0b10010, 0b10010, 0b01100],
+    // 'V'
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+    // 'W'
+    [0b10001, 0b10001, 0b10101, 0b11011, 0b10001],
+    // 'X'
+    [0b10010, 0b10010, 0b01100, 0b10010, 0b10010],
+    // 'Y'
+    [0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+    // 'Z'
+    [0b11110, 0b00100, 0b01000, 0b10000, 0b11110],
+    // '['
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01110],
+    // '\\'
+    [0b10000, 0b01000, 0b00100, 0b00010, 0b00001],
+    // ']'
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b01110],
+    // '^'
+    [0b00100, 0b01010, 0b00000, 0b00000, 0b00000],
+    // '_'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+    // '`'
+    [0b01000, 0b00100, 0b00000, 0b00000, 0b00000],
+    // 'a'
+    [0b00000, 0b01110, 0b10010, 0b10010, 0b01111],
+    // 'b'
+    [0b10000, 0b10000, 0b11100, 0b10010, 0b11100],
+    // 'c'
+    [0b00000, 0b01110, 0b10000, 0b10000, 0b01110],
+    // 'd'
+    [0b00010, 0b00010, 0b01110, 0b10010, 0b01110],
+    // 'e'
+    [0b01100, 0b10010, 0b11100, 0b10000, 0b01110],
+    // 'f'
+    [0b00110, 0b01000, 0b11100, 0b01000, 0b01000],
+    // 'g'
+    [0b01110, 0b10010, 0b01110, 0b00010, 0b01100],
+    // 'h'
+    [0b10000, 0b10000, 0b11100, 0b10010, 0b10010],
+    // 'i'
+    [0b01000, 0b00000, 0b01000, 0b01000, 0b01000],
+    // 'j'
+    [0b00010, 0b00000, 0b00010, 0b10010, 0b01100],
+    // 'k'
+    [0b10000, 0b10100, 0b11000, 0b10100, 0b10010],
+    // 'l'
+    [0b01000, 0b01000, 0b01000, 0b01000, 0b00110],
+    // 'm'
+    [0b00000, 0b11010, 0b10101, 0b10101, 0b10101],
+    // 'n'
+    [0b00000, 0b11100, 0b10010, 0b10010, 0b10010],
+    // 'o'
+    [0b00000, 0b01100, 0b10010, 0b10010, 0b01100],
+    // 'p'
+    [0b00000, 0b11100, 0b10010, 0b11100, 0b10000],
+    // 'q'
+    [0b00000, 0b01110, 0b10010, 0b01110, 0b00010],
+    // 'r'
+    [0b00000, 0b01110, 0b10000, 0b10000, 0b10000],
+    // 's'
+    [0b00000, 0b00110, 0b01000, 0b00100, 0b11000],
+    // 't'
+    [0b01000, 0b01110, 0b01000, 0b01000, 0b00110],
+    // 'u'
+    [0b00000, 0b10010, 0b10010, 0b10010, 0b01110],
+    // 'v'
+    [0b00000, 0b10001, 0b10001, 0b01010, 0b00100],
+    // 'w'
+    [0b00000, 0b10001, 0b10101, 0b10101, 0b01010],
+    // 'x'
+    [0b00000, 0b10010, 0b01100, 0b01100, 0b10010],
+    // 'y'
+    [0b00000, 0b10010, 0b01110, 0b00010, 0b01100],
+    // 'z'
+    [0b00000, 0b11110, 0b00100, 0b01000, 0b11110],
+    // '{'
+    [0b00110, 0b00100, 0b01000, 0b00100, 0b00110],
+    // '|'
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+    // '}'
+    [0b01100, 0b00100, 0b00010, 0b00100, 0b01100],
+    // '~'
+    [0b00000, 0b00000, 0b01000, 0b10101, 0b00010],
+];
+/// Drawn for characters the font doesn't have
+const UNKNOWN: [u8; 5] = GLYPHS[b'?' as usize - FIRST as usize];
+const FIRST: char = ' ';
+const LAST: char = '~';
+
+/// Rows of the glyph for `c`, or of `?` if the font doesn't have one
+pub const fn glyph(c: char) -> [u8; 5] {
+    if c < FIRST || c > LAST {
+        return UNKNOWN;
+    }
+    GLYPHS[c as usize - FIRST as usize]
+}
+/// Column `x` of the glyph for `c`, bit `y` set where row `y` is lit
+pub const fn glyph_column(c: char, x: usize) -> u8 {
+    let rows = glyph(c);
+    let mut column = 0;
+    let mut y = 0;
+    while y < 5 {
+        if rows[y] & (0b10000 >> x) != 0 {
+            column |= 1 << y;
+        }
+        y += 1;
+    }
+    column
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyphs_are_indexed_by_character() {
+        assert_eq!(glyph(' '), [0; 5]);
+        assert_eq!(glyph('1'), [0b00100, 0b01100, 0b00100, 0b00100, 0b01110]);
+        assert_eq!(glyph('\u{e9}'), glyph('?'));
+        assert_eq!(glyph('\n'), glyph('?'));
+        assert_ne!(glyph('~'), glyph('?'));
+    }
+
+    #[test]
+    fn columns_are_read_top_down() {
+        assert_eq!(glyph_column('L', 0), 0b11111);
+        assert_eq!(glyph_column('L', 1), 0b10000);
+        assert_eq!(glyph_column('T', 2), 0b11111);
+        assert_eq!(glyph_column('T', 4), 0b00001);
+    }
+}
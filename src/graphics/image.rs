@@ -0,0 +1,310 @@
+//! Images for the LED matrix.
+use super::font;
+
+/// Brightest level of a pixel; 0 is off
+pub const MAX_BRIGHTNESS: u8 = 9;
+
+/// 5x5 image, one brightness level from 0 to [`MAX_BRIGHTNESS`] per pixel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Image5x5([[u8; 5]; 5]);
+impl Image5x5 {
+    /// All pixels off
+    pub const BLANK: Image5x5 = Image5x5([[0; 5]; 5]);
+    /// A heart
+    pub const HEART: Image5x5 = Image5x5::from_art(
+        ".#.#.
+         #####
+         #####
+         .###.
+         ..#..",
+    );
+    /// A smiling face
+    pub const HAPPY: Image5x5 = Image5x5::from_art(
+        ".....
+         .#.#.
+         .....
+         #...#
+         .###.",
+    );
+    /// A frowning face
+    pub const SAD: Image5x5 = Image5x5::from_art(
+        ".....
+         .#.#.
+         .....
+         .###.
+         #...#",
+    );
+    /// A tick
+    pub const YES: Image5x5 = Image5x5::from_art(
+        ".....
+         ....#
+         ...#.
+         #.#..
+         .#...",
+    );
+    /// A cross
+    pub const NO: Image5x5 = Image5x5::from_art(
+        "#...#
+         .#.#.
+         ..#..
+         .#.#.
+         #...#",
+    );
+
+    /// Image from rows of brightness levels, top row first, levels above
+    /// [`MAX_BRIGHTNESS`] being clamped to it
+    pub const fn new(rows: [[u8; 5]; 5]) -> Self {
+        let mut image = Image5x5(rows);
+        let mut y = 0;
+        while y < 5 {
+            let mut x = 0;
+            while x < 5 {
+                if image.0[y][x] > MAX_BRIGHTNESS {
+                    image.0[y][x] = MAX_BRIGHTNESS;
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+        image
+    }
+    /// Image from rows of five bits, the most significant of the five being
+    /// the leftmost pixel, lit at full brightness
+    pub const fn from_bits(rows: [u8; 5]) -> Self {
+        let mut image = Image5x5::BLANK;
+        let mut y = 0;
+        while y < 5 {
+            let mut x = 0;
+            while x < 5 {
+                if rows[y] & (0b10000 >> x) != 0 {
+                    image.0[y][x] = MAX_BRIGHTNESS;
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+        image
+    }
+    /// Image drawn as 25 pixels, row by row: `.` is off, `#` is full
+    /// brightness and a digit is that brightness level; whitespace is
+    /// skipped
+    ///
+    /// Panics, at compile time when used in a constant, if `art` holds any
+    /// other character or not exactly 25 pixels.
+    pub const fn from_art(art: &str) -> Self {
+        let bytes = art.as_bytes();
+        let mut image = Image5x5::BLANK;
+        let mut pixels = 0;
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let level = match bytes[idx] {
+                b' ' | b'\t' | b'\r' | b'\n' => {
+                    idx += 1;
+                    continue;
+                }
+                b'.' => 0,
+                b'#' => MAX_BRIGHTNESS,
+                digit @ b'0'..=b'9' => digit - b'0',
+                _ => panic!("pixels are drawn with `.`, `#` or a brightness digit"),
+            };
+            assert!(pixels < 25, "an image has 25 pixels, found more");
+            image.0[pixels / 5][pixels % 5] = level;
+            pixels += 1;
+            idx += 1;
+        }
+        assert!(pixels == 25, "an image has 25 pixels, found fewer");
+        image
+    }
+    /// All pixels at `brightness`, clamped to [`MAX_BRIGHTNESS`]
+    pub const fn filled(brightness: u8) -> Self {
+        Image5x5::new([[brightness; 5]; 5])
+    }
+    /// The glyph of `c` in the built-in font
+    pub const fn char(c: char) -> Self {
+        Image5x5::from_bits(font::glyph(c))
+    }
+    /// Brightness of the pixel in column `x` of row `y`
+    #[inline]
+    pub const fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.0[y][x]
+    }
+    /// Set the brightness of the pixel in column `x` of row `y`, clamped to
+    /// [`MAX_BRIGHTNESS`]
+    #[inline]
+    pub fn set_pixel(&mut self, x: usize, y: usize, brightness: u8) {
+        self.0[y][x] = brightness.min(MAX_BRIGHTNESS)
+    }
+    /// Brightness levels of row `y`, leftmost first
+    #[inline]
+    pub const fn row(&self, y: usize) -> [u8; 5] {
+        self.0[y]
+    }
+    /// Brightness of the brightest pixel
+    pub const fn brightest(&self) -> u8 {
+        let mut brightest = 0;
+        let mut idx = 0;
+        while idx < 25 {
+            if self.0[idx / 5][idx % 5] > brightest {
+                brightest = self.0[idx / 5][idx % 5];
+            }
+            idx += 1;
+        }
+        brightest
+    }
+    /// The image dimmed so that full brightness becomes `brightness`
+    pub const fn scaled(self, brightness: u8) -> Self {
+        let brightness = if brightness > MAX_BRIGHTNESS {
+            MAX_BRIGHTNESS
+        } else {
+            brightness
+        };
+        let mut image = self;
+        let mut idx = 0;
+        while idx < 25 {
+            let pixel = &mut image.0[idx / 5][idx % 5];
+            *pixel = div_round(*pixel as u16 * brightness as u16);
+            idx += 1;
+        }
+        image
+    }
+    /// The image with every pixel's brightness flipped, off becoming full
+    pub const fn inverted(self) -> Self {
+        let mut image = self;
+        let mut idx = 0;
+        while idx < 25 {
+            let pixel = &mut image.0[idx / 5][idx % 5];
+            *pixel = MAX_BRIGHTNESS - *pixel;
+            idx += 1;
+        }
+        image
+    }
+    /// Cross-fade from `self` to `other`, `amount` going from 0, all of
+    /// `self`, to [`MAX_BRIGHTNESS`], all of `other`
+    pub const fn blend(self, other: Image5x5, amount: u8) -> Self {
+        let amount = if amount > MAX_BRIGHTNESS {
+            MAX_BRIGHTNESS
+        } else {
+            amount
+        };
+        let mut image = self;
+        let mut idx = 0;
+        while idx < 25 {
+            let (y, x) = (idx / 5, idx % 5);
+            image.0[y][x] = div_round(
+                self.0[y][x] as u16 * (MAX_BRIGHTNESS - amount) as u16
+                    + other.0[y][x] as u16 * amount as u16,
+            );
+            idx += 1;
+        }
+        image
+    }
+    /// Both images on top of each other, each pixel as bright as the
+    /// brighter of the two
+    pub const fn overlay(self, other: Image5x5) -> Self {
+        let mut image = self;
+        let mut idx = 0;
+        while idx < 25 {
+            let (y, x) = (idx / 5, idx % 5);
+            if other.0[y][x] > image.0[y][x] {
+                image.0[y][x] = other.0[y][x];
+            }
+            idx += 1;
+        }
+        image
+    }
+    /// The image moved `dx` columns right and `dy` rows down, pixels moved
+    /// in from outside being off
+    pub const fn shifted(self, dx: isize, dy: isize) -> Self {
+        let mut image = Image5x5::BLANK;
+        let mut idx = 0;
+        while idx < 25 {
+            let (y, x) = ((idx / 5) as isize, (idx % 5) as isize);
+            let (from_y, from_x) = (y - dy, x - dx);
+            if from_y >= 0 && from_y < 5 && from_x >= 0 && from_x < 5 {
+                image.0[y as usize][x as usize] = self.0[from_y as usize][from_x as usize];
+            }
+            idx += 1;
+        }
+        image
+    }
+}
+/// `value / MAX_BRIGHTNESS`, rounded to the nearest level
+const fn div_round(value: u16) -> u8 {
+    ((value + MAX_BRIGHTNESS as u16 / 2) / MAX_BRIGHTNESS as u16) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_light_pixels_at_full_brightness() {
+        let image = Image5x5::from_bits([0b10000, 0, 0, 0, 0b00001]);
+        assert_eq!(image.pixel(0, 0), MAX_BRIGHTNESS);
+        assert_eq!(image.pixel(4, 4), MAX_BRIGHTNESS);
+        assert_eq!(image.pixel(4, 0), 0);
+        assert_eq!(Image5x5::char('I').row(1), [0, 9, 0, 0, 0]);
+    }
+
+    #[test]
+    fn art_is_read_row_by_row() {
+        assert_eq!(
+            Image5x5::from_art("#.... ..... ..5.. ..... ....1"),
+            Image5x5::new([
+                [9, 0, 0, 0, 0],
+                [0; 5],
+                [0, 0, 5, 0, 0],
+                [0; 5],
+                [0, 0, 0, 0, 1],
+            ])
+        );
+        assert_eq!(Image5x5::HEART.row(0), [0, 9, 0, 9, 0]);
+        assert_eq!(
+            Image5x5::NO.overlay(Image5x5::HEART).row(0),
+            [9, 9, 0, 9, 9]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "found fewer")]
+    fn art_must_have_25_pixels() {
+        Image5x5::from_art("#####");
+    }
+
+    #[test]
+    fn brightness_ops() {
+        let image = Image5x5::from_art("9630. ..... ..... ..... .....");
+        assert_eq!(image.brightest(), 9);
+        assert_eq!(image.scaled(3).row(0), [3, 2, 1, 0, 0]);
+        assert_eq!(image.scaled(MAX_BRIGHTNESS), image);
+        assert_eq!(image.inverted().row(0), [0, 3, 6, 9, 9]);
+        assert_eq!(image.inverted().inverted(), image);
+        assert_eq!(Image5x5::filled(4).brightest(), 4);
+    }
+
+    #[test]
+    fn blend_fades_between_images() {
+        let on = Image5x5::filled(MAX_BRIGHTNESS);
+        assert_eq!(Image5x5::BLANK.blend(on, 0), Image5x5::BLANK);
+        assert_eq!(Image5x5::BLANK.blend(on, 3), Image5x5::filled(3));
+        assert_eq!(Image5x5::BLANK.blend(on, 20), on);
+        assert_eq!(Image5x5::HEART.blend(Image5x5::HEART, 5), Image5x5::HEART);
+    }
+
+    #[test]
+    fn shift_moves_pixels_and_drops_the_edge() {
+        let dot = Image5x5::from_art("#.... ..... ..... ..... .....");
+        assert_eq!(dot.shifted(2, 1).pixel(2, 1), MAX_BRIGHTNESS);
+        assert_eq!(dot.shifted(-1, 0), Image5x5::BLANK);
+        assert_eq!(dot.shifted(4, 4).shifted(-4, -4), dot);
+    }
+
+    #[test]
+    fn brightness_is_clamped() {
+        let mut image = Image5x5::new([[12; 5]; 5]);
+        assert_eq!(image.pixel(2, 2), MAX_BRIGHTNESS);
+        image.set_pixel(2, 2, 3);
+        image.set_pixel(3, 2, 200);
+        assert_eq!(image.row(2), [9, 9, 3, 9, 9]);
+    }
+}
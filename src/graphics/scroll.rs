@@ -0,0 +1,135 @@
+//! Text scrolling from right to left.
+use super::{glyph_column, Image5x5, MAX_BRIGHTNESS};
+
+/// Columns taken by each character, including the gap after it
+const CHAR_COLUMNS: usize = 6;
+
+/// Text moving across the screen from right to left, one column at a time
+///
+/// The text enters at the right edge of a blank screen and is done once its
+/// last column has left at the left edge. As an [`Iterator`] it yields the
+/// image of every column step, ignoring the speed.
+#[derive(Clone, Debug)]
+pub struct Scroller<'a> {
+    text: &'a str,
+    /// Columns of the text that have entered the screen so far
+    column: usize,
+    ticks_per_column: u16,
+    ticks: u16,
+}
+impl<'a> Scroller<'a> {
+    /// Scroll `text`, moving one column every `ticks_per_column` ticks
+    pub const fn new(text: &'a str, ticks_per_column: u16) -> Self {
+        Scroller {
+            text,
+            column: 0,
+            ticks_per_column,
+            ticks: 0,
+        }
+    }
+    /// Ticks each column step lasts
+    pub fn speed(&self) -> u16 {
+        self.ticks_per_column
+    }
+    /// Change the ticks each column step lasts, from the next step on
+    pub fn set_speed(&mut self, ticks_per_column: u16) {
+        self.ticks_per_column = ticks_per_column
+    }
+    /// Whether the whole text has scrolled off
+    pub fn is_finished(&self) -> bool {
+        self.column >= self.columns() + 5
+    }
+    /// Start over with the text off screen
+    pub fn reset(&mut self) {
+        self.column = 0;
+        self.ticks = 0;
+    }
+    /// Advance one tick, returning whether the image changed
+    pub fn tick(&mut self) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        self.ticks += 1;
+        if self.ticks < self.ticks_per_column {
+            return false;
+        }
+        self.ticks = 0;
+        self.column += 1;
+        true
+    }
+    /// The part of the text on screen, blank once it has scrolled off
+    pub fn image(&self) -> Image5x5 {
+        let mut image = Image5x5::BLANK;
+        for x in 0..5 {
+            let Some(column) = (self.column + x).checked_sub(5) else {
+                continue;
+            };
+            if column % CHAR_COLUMNS == CHAR_COLUMNS - 1 {
+                continue;
+            }
+            let Some(c) = self.text.chars().nth(column / CHAR_COLUMNS) else {
+                continue;
+            };
+            let bits = glyph_column(c, column % CHAR_COLUMNS);
+            for y in 0..5 {
+                if bits & (1 << y) != 0 {
+                    image.set_pixel(x, y, MAX_BRIGHTNESS);
+                }
+            }
+        }
+        image
+    }
+    /// Columns the text takes up
+    fn columns(&self) -> usize {
+        self.text.chars().count() * CHAR_COLUMNS
+    }
+}
+impl Iterator for Scroller<'_> {
+    type Item = Image5x5;
+    fn next(&mut self) -> Option<Image5x5> {
+        if self.is_finished() {
+            return None;
+        }
+        let image = self.image();
+        self.column += 1;
+        self.ticks = 0;
+        Some(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_enters_right_and_leaves_left() {
+        let frames: Vec<_> = Scroller::new("I", 1).collect();
+        // Blank, five columns of the glyph and the gap, blank again
+        assert_eq!(frames.len(), 11);
+        assert_eq!(frames[0], Image5x5::BLANK);
+        assert_eq!(frames[1].row(0), [0, 0, 0, 0, 9]);
+        assert_eq!(frames[4].row(0), [0, 9, 9, 9, 0]);
+        assert_eq!(frames[5], Image5x5::char('I'));
+        assert_eq!(frames[6].row(0), [9, 9, 0, 0, 0]);
+        assert_eq!(frames[10], Image5x5::BLANK);
+    }
+
+    #[test]
+    fn speed_sets_ticks_per_column() {
+        let mut scroller = Scroller::new("AB", 3);
+        assert!(!scroller.tick());
+        assert!(!scroller.tick());
+        assert!(scroller.tick());
+        assert_eq!(scroller.image().row(2), [0, 0, 0, 0, 9]);
+        scroller.set_speed(1);
+        let mut ticks = 1;
+        while scroller.tick() {
+            ticks += 1;
+        }
+        assert_eq!(ticks, 2 * CHAR_COLUMNS + 5);
+        assert!(scroller.is_finished());
+        assert_eq!(scroller.image(), Image5x5::BLANK);
+        scroller.reset();
+        assert_eq!(scroller.count(), 2 * CHAR_COLUMNS + 5);
+    }
+}
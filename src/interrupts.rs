@@ -0,0 +1,188 @@
+//! Interrupts of the nRF52833.
+//!
+//! Handlers are defined with the re-exported `#[interrupt]` attribute of
+//! `cortex-m-rt`, named after the [`Interrupt`] they handle, and are entered
+//! in the vector table below. Interrupts without a handler go to the default
+//! one.
+//!
+//! ```ignore
+//! use microrust::interrupt;
+//!
+//! #[interrupt]
+//! fn GPIOTE() {
+//!     microrust::gpiote::on_interrupt();
+//! }
+//! ```
+use cortex_m::interrupt::InterruptNumber;
+
+/// Interrupt lines of the nRF52833, numbered as in the vector table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+#[repr(u16)]
+pub enum Interrupt {
+    POWER_CLOCK = 0,
+    RADIO = 1,
+    UARTE0_UART0 = 2,
+    SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0 = 3,
+    SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1 = 4,
+    NFCT = 5,
+    GPIOTE = 6,
+    SAADC = 7,
+    TIMER0 = 8,
+    TIMER1 = 9,
+    TIMER2 = 10,
+    RTC0 = 11,
+    TEMP = 12,
+    RNG = 13,
+    ECB = 14,
+    CCM_AAR = 15,
+    WDT = 16,
+    RTC1 = 17,
+    QDEC = 18,
+    COMP_LPCOMP = 19,
+    SWI0_EGU0 = 20,
+    SWI1_EGU1 = 21,
+    SWI2_EGU2 = 22,
+    SWI3_EGU3 = 23,
+    SWI4_EGU4 = 24,
+    SWI5_EGU5 = 25,
+    TIMER3 = 26,
+    TIMER4 = 27,
+    PWM0 = 28,
+    PDM = 29,
+    MWU = 32,
+    PWM1 = 33,
+    PWM2 = 34,
+    SPIM2_SPIS2_SPI2 = 35,
+    RTC2 = 36,
+    I2S = 37,
+    FPU = 38,
+    USBD = 39,
+    UARTE1 = 40,
+    PWM3 = 45,
+    SPIM3 = 47,
+}
+unsafe impl InterruptNumber for Interrupt {
+    #[inline]
+    fn number(self) -> u16 {
+        self as u16
+    }
+}
+
+#[cfg(target_os = "none")]
+mod vectors {
+    extern "C" {
+        fn POWER_CLOCK();
+        fn RADIO();
+        fn UARTE0_UART0();
+        fn SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0();
+        fn SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1();
+        fn NFCT();
+        fn GPIOTE();
+        fn SAADC();
+        fn TIMER0();
+        fn TIMER1();
+        fn TIMER2();
+        fn RTC0();
+        fn TEMP();
+        fn RNG();
+        fn ECB();
+        fn CCM_AAR();
+        fn WDT();
+        fn RTC1();
+        fn QDEC();
+        fn COMP_LPCOMP();
+        fn SWI0_EGU0();
+        fn SWI1_EGU1();
+        fn SWI2_EGU2();
+        fn SWI3_EGU3();
+        fn SWI4_EGU4();
+        fn SWI5_EGU5();
+        fn TIMER3();
+        fn TIMER4();
+        fn PWM0();
+        fn PDM();
+        fn MWU();
+        fn PWM1();
+        fn PWM2();
+        fn SPIM2_SPIS2_SPI2();
+        fn RTC2();
+        fn I2S();
+        fn FPU();
+        fn USBD();
+        fn UARTE1();
+        fn PWM3();
+        fn SPIM3();
+    }
+
+    /// Entry of the vector table, either a handler or a reserved slot
+    pub union Vector {
+        handler: unsafe extern "C" fn(),
+        reserved: usize,
+    }
+
+    #[link_section = ".vector_table.interrupts"]
+    #[no_mangle]
+    pub static __INTERRUPTS: [Vector; 48] = [
+        Vector {
+            handler: POWER_CLOCK,
+        },
+        Vector { handler: RADIO },
+        Vector {
+            handler: UARTE0_UART0,
+        },
+        Vector {
+            handler: SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0,
+        },
+        Vector {
+            handler: SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1,
+        },
+        Vector { handler: NFCT },
+        Vector { handler: GPIOTE },
+        Vector { handler: SAADC },
+        Vector { handler: TIMER0 },
+        Vector { handler: TIMER1 },
+        Vector { handler: TIMER2 },
+        Vector { handler: RTC0 },
+        Vector { handler: TEMP },
+        Vector { handler: RNG },
+        Vector { handler: ECB },
+        Vector { handler: CCM_AAR },
+        Vector { handler: WDT },
+        Vector { handler: RTC1 },
+        Vector { handler: QDEC },
+        Vector {
+            handler: COMP_LPCOMP,
+        },
+        Vector { handler: SWI0_EGU0 },
+        Vector { handler: SWI1_EGU1 },
+        Vector { handler: SWI2_EGU2 },
+        Vector { handler: SWI3_EGU3 },
+        Vector { handler: SWI4_EGU4 },
+        Vector { handler: SWI5_EGU5 },
+        Vector { handler: TIMER3 },
+        Vector { handler: TIMER4 },
+        Vector { handler: PWM0 },
+        Vector { handler: PDM },
+        Vector { reserved: 0 },
+        Vector { reserved: 0 },
+        Vector { handler: MWU },
+        Vector { handler: PWM1 },
+        Vector { handler: PWM2 },
+        Vector {
+            handler: SPIM2_SPIS2_SPI2,
+        },
+        Vector { handler: RTC2 },
+        Vector { handler: I2S },
+        Vector { handler: FPU },
+        Vector { handler: USBD },
+        Vector { handler: UARTE1 },
+        Vector { reserved: 0 },
+        Vector { reserved: 0 },
+        Vector { reserved: 0 },
+        Vector { reserved: 0 },
+        Vector { handler: PWM3 },
+        Vector { reserved: 0 },
+        Vector { handler: SPIM3 },
+    ];
+}
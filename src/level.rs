@@ -0,0 +1,295 @@
+//! Logic levels and sets of pins.
+//!
+//! A [`Level`] is the state of a single pin, a [`PinMask`] is a set of pins
+//! of one port, laid out like the bits of the port's registers so it can be
+//! written to `OUTSET`, `DIRCLR`, `LATCH` and friends as is.
+use core::fmt;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not, Sub};
+
+/// Logic level of a pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Level {
+    Low,
+    High,
+}
+impl Level {
+    #[inline]
+    pub const fn is_high(self) -> bool {
+        matches!(self, Level::High)
+    }
+    #[inline]
+    pub const fn is_low(self) -> bool {
+        matches!(self, Level::Low)
+    }
+}
+impl Not for Level {
+    type Output = Level;
+    #[inline]
+    fn not(self) -> Level {
+        match self {
+            Level::Low => Level::High,
+            Level::High => Level::Low,
+        }
+    }
+}
+impl From<bool> for Level {
+    #[inline]
+    fn from(high: bool) -> Self {
+        if high {
+            Level::High
+        } else {
+            Level::Low
+        }
+    }
+}
+impl From<Level> for bool {
+    #[inline]
+    fn from(level: Level) -> Self {
+        level.is_high()
+    }
+}
+/// `Low` is 0, `High` is 1, as in a register bit
+impl From<Level> for u32 {
+    #[inline]
+    fn from(level: Level) -> Self {
+        level.is_high() as u32
+    }
+}
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Low => "Low",
+            Level::High => "High",
+        })
+    }
+}
+
+/// Set of pins of a port, bit `n` standing for pin `n`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PinMask(u32);
+impl PinMask {
+    /// No pins
+    pub const EMPTY: PinMask = PinMask(0);
+    /// All 32 pins a port register can address
+    pub const ALL: PinMask = PinMask(u32::MAX);
+
+    /// Mask with the bits of `bits`
+    #[inline]
+    pub const fn new(bits: u32) -> Self {
+        PinMask(bits)
+    }
+    /// Mask holding only `pin`, panicking if it is not below 32
+    #[inline]
+    pub const fn pin(pin: usize) -> Self {
+        assert!(pin < 32, "pin index out of range");
+        PinMask(1 << pin)
+    }
+    /// Mask holding each of `pins`
+    pub const fn from_pins(pins: &[usize]) -> Self {
+        let mut mask = PinMask::EMPTY;
+        let mut idx = 0;
+        while idx < pins.len() {
+            mask = mask.with(pins[idx]);
+            idx += 1;
+        }
+        mask
+    }
+    /// The raw bits, as written to a register
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+    #[inline]
+    pub const fn contains(self, pin: usize) -> bool {
+        pin < 32 && self.0 & (1 << pin) != 0
+    }
+    /// Level of `pin` in a register value read as a mask
+    #[inline]
+    pub const fn level(self, pin: usize) -> Level {
+        if self.contains(pin) {
+            Level::High
+        } else {
+            Level::Low
+        }
+    }
+    /// The mask with `pin` added
+    #[inline]
+    pub const fn with(self, pin: usize) -> Self {
+        PinMask(self.0 | PinMask::pin(pin).0)
+    }
+    /// The mask with `pin` removed
+    #[inline]
+    pub const fn without(self, pin: usize) -> Self {
+        PinMask(self.0 & !PinMask::pin(pin).0)
+    }
+    #[inline]
+    pub const fn union(self, other: PinMask) -> Self {
+        PinMask(self.0 | other.0)
+    }
+    #[inline]
+    pub const fn intersection(self, other: PinMask) -> Self {
+        PinMask(self.0 & other.0)
+    }
+    /// Pins of `self` that are not in `other`
+    #[inline]
+    pub const fn difference(self, other: PinMask) -> Self {
+        PinMask(self.0 & !other.0)
+    }
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    /// Number of pins in the mask
+    #[inline]
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+    /// The pins in the mask, lowest first
+    #[inline]
+    pub fn iter(self) -> Pins {
+        Pins(self.0)
+    }
+}
+impl From<u32> for PinMask {
+    #[inline]
+    fn from(bits: u32) -> Self {
+        PinMask(bits)
+    }
+}
+impl From<PinMask> for u32 {
+    #[inline]
+    fn from(mask: PinMask) -> Self {
+        mask.0
+    }
+}
+impl BitOr for PinMask {
+    type Output = PinMask;
+    #[inline]
+    fn bitor(self, rhs: PinMask) -> PinMask {
+        self.union(rhs)
+    }
+}
+impl BitOrAssign for PinMask {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: PinMask) {
+        *self = self.union(rhs)
+    }
+}
+impl BitAnd for PinMask {
+    type Output = PinMask;
+    #[inline]
+    fn bitand(self, rhs: PinMask) -> PinMask {
+        self.intersection(rhs)
+    }
+}
+impl BitAndAssign for PinMask {
+    #[inline]
+    fn bitand_assign(&mut self, rhs: PinMask) {
+        *self = self.intersection(rhs)
+    }
+}
+impl BitXor for PinMask {
+    type Output = PinMask;
+    #[inline]
+    fn bitxor(self, rhs: PinMask) -> PinMask {
+        PinMask(self.0 ^ rhs.0)
+    }
+}
+impl Sub for PinMask {
+    type Output = PinMask;
+    #[inline]
+    fn sub(self, rhs: PinMask) -> PinMask {
+        self.difference(rhs)
+    }
+}
+impl Not for PinMask {
+    type Output = PinMask;
+    #[inline]
+    fn not(self) -> PinMask {
+        PinMask(!self.0)
+    }
+}
+impl FromIterator<usize> for PinMask {
+    fn from_iter<I: IntoIterator<Item = usize>>(pins: I) -> Self {
+        pins.into_iter().fold(PinMask::EMPTY, PinMask::with)
+    }
+}
+impl IntoIterator for PinMask {
+    type Item = usize;
+    type IntoIter = Pins;
+    #[inline]
+    fn into_iter(self) -> Pins {
+        self.iter()
+    }
+}
+
+/// Iterator over the pins of a [`PinMask`], from [`PinMask::iter`]
+#[derive(Clone, Debug)]
+pub struct Pins(u32);
+impl Iterator for Pins {
+    type Item = usize;
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let pin = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(pin)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+impl ExactSizeIterator for Pins {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_round_trips() {
+        for level in [Level::Low, Level::High] {
+            assert_eq!(Level::from(bool::from(level)), level);
+            assert_eq!(u32::from(level), level.is_high() as u32);
+            assert_eq!(PinMask::new(u32::from(level) << 7).level(7), level);
+            assert_eq!(!!level, level);
+        }
+        assert_eq!(!Level::Low, Level::High);
+        assert_eq!(Level::High.to_string(), "High");
+    }
+
+    #[test]
+    fn mask_round_trips() {
+        let mask = PinMask::from_pins(&[0, 5, 31]);
+        assert_eq!(mask.bits(), 1 | 1 << 5 | 1 << 31);
+        assert_eq!(PinMask::from(u32::from(mask)), mask);
+        assert_eq!(mask.iter().collect::<Vec<_>>(), [0, 5, 31]);
+        assert_eq!(mask.iter().collect::<PinMask>(), mask);
+        assert_eq!(mask.iter().len(), mask.len());
+        assert_eq!(PinMask::pin(9), PinMask::from_pins(&[9]));
+        assert!(PinMask::EMPTY.is_empty());
+        assert_eq!(PinMask::ALL.len(), 32);
+    }
+
+    #[test]
+    fn mask_set_ops() {
+        let a = PinMask::from_pins(&[1, 2, 3]);
+        let b = PinMask::from_pins(&[3, 4]);
+        assert_eq!(a | b, PinMask::from_pins(&[1, 2, 3, 4]));
+        assert_eq!(a & b, PinMask::pin(3));
+        assert_eq!(a - b, PinMask::from_pins(&[1, 2]));
+        assert_eq!(a ^ b, PinMask::from_pins(&[1, 2, 4]));
+        assert_eq!(!a & b, PinMask::pin(4));
+        assert_eq!(a.with(7).without(1), PinMask::from_pins(&[2, 3, 7]));
+        assert!(a.contains(2) && !a.contains(4) && !a.contains(40));
+    }
+
+    #[test]
+    #[should_panic(expected = "pin index out of range")]
+    fn mask_rejects_pins_past_31() {
+        PinMask::pin(32);
+    }
+}
@@ -0,0 +1,924 @@
+#![cfg_attr(not(test), no_std)]
+
+pub mod board;
+pub mod button;
+pub mod clock;
+pub mod display;
+mod error;
+pub mod gpio;
+pub mod gpiote;
+pub mod graphics;
+mod interrupts;
+mod level;
+pub mod mem;
+mod peripherals;
+pub mod rtc;
+pub mod spim;
+pub mod time;
+pub mod timer;
+pub mod twim;
+pub mod uarte;
+
+pub use cortex_m_rt::interrupt;
+pub use error::{Error, ErrorKind};
+pub use interrupts::Interrupt;
+/// Lets `#[interrupt]` check handler names against [`Interrupt`]
+pub use interrupts::Interrupt as interrupt;
+pub use level::{Level, PinMask, Pins};
+pub use peripherals::Peripherals;
+
+use core::ops::RangeToInclusive;
+
+/// Busy-wait for about `cycles` CPU cycles; on the host there is nothing to
+/// wait for
+pub(crate) fn pause(cycles: u32) {
+    #[cfg(target_os = "none")]
+    cortex_m::asm::delay(cycles);
+    #[cfg(not(target_os = "none"))]
+    let _ = cycles;
+}
+
+pub trait Addressable {
+    const ADDR: usize;
+}
+
+pub trait Peripheral {}
+pub struct GPIO;
+pub trait PortKind {}
+impl PortKind for GPIO {}
+pub trait Port: Addressable {
+    const RANGE: RangeToInclusive<u8>;
+    #[inline]
+    fn is_valid(pin: usize) -> bool {
+        pin <= Self::RANGE.end as usize
+    }
+}
+
+/// A register of the peripheral `Instance`, at `OFFSET` from its base address
+pub trait Register {
+    type Instance: Addressable;
+    const OFFSET: usize;
+}
+impl<R: Register> Addressable for R {
+    const ADDR: usize = R::Instance::ADDR + R::OFFSET;
+}
+pub trait Write: Register + Addressable {
+    /// Store `value` to the whole register
+    #[inline]
+    fn write_raw(value: u32) {
+        unsafe { mem::write(Self::ADDR, value) }
+    }
+    /// Store `pins` to the whole register, e.g. to set or clear them in a
+    /// write-1-to-set or write-1-to-clear register
+    #[inline]
+    fn write_pins(pins: PinMask)
+    where
+        Self::Instance: Port,
+    {
+        Self::write_raw(pins.bits())
+    }
+    /// Read-modify-write of a single bit, so only available on registers that
+    /// can also be read
+    #[inline]
+    fn write_pin(pin: usize, value: Level) -> Result<(), Error>
+    where
+        Self: Read,
+        Self::Instance: Port,
+    {
+        if !Self::Instance::is_valid(pin) {
+            return Err(ErrorKind::BadIndex.into());
+        };
+
+        let pins = Self::read_pins();
+
+        // If the bits mismatch
+        if pins.level(pin) != value {
+            // Flip only that bit
+            Self::write_pins(pins ^ PinMask::pin(pin));
+        }
+        Ok(())
+    }
+}
+
+pub trait Read: Register + Addressable {
+    /// Load the whole register
+    #[inline]
+    fn read_raw() -> u32 {
+        unsafe { mem::read(Self::ADDR) }
+    }
+    /// Load the whole register as a set of pins
+    #[inline]
+    fn read_pins() -> PinMask
+    where
+        Self::Instance: Port,
+    {
+        Self::read_raw().into()
+    }
+    #[inline]
+    fn read_pin(pin: usize) -> Result<Level, Error>
+    where
+        Self::Instance: Port,
+    {
+        if !Self::Instance::is_valid(pin) {
+            return Err(ErrorKind::BadIndex.into());
+        };
+        Ok(Self::read_pin_unchecked(pin))
+    }
+    /// Level of bit `pin`, which is not checked against the port
+    #[inline]
+    fn read_pin_unchecked(pin: usize) -> Level {
+        PinMask::from(Self::read_raw()).level(pin)
+    }
+}
+
+/// `COUNT` registers of the peripheral `Instance`, the first at `OFFSET` from
+/// its base address and each following one `STRIDE` bytes further
+pub trait RegisterArray<const COUNT: usize> {
+    type Instance: Addressable;
+    const OFFSET: usize;
+    const STRIDE: usize = 4;
+    const ADDRS: [usize; COUNT] = const {
+        let mut idx = 0;
+        let mut addrs = [Self::Instance::ADDR + Self::OFFSET; COUNT];
+        while idx < COUNT {
+            addrs[idx] += idx * Self::STRIDE;
+            idx += 1;
+        }
+        addrs
+    };
+}
+
+pub trait WriteArray<const COUNT: usize>: RegisterArray<COUNT> {
+    #[inline]
+    fn write_array(index: usize, value: u32) -> Result<(), Error> {
+        if index >= COUNT {
+            return Err(ErrorKind::BadIndex.into());
+        }
+        Self::write_array_unchecked(index, value);
+        Ok(())
+    }
+    /// Store `value` to element `index`, panicking if it is out of range
+    #[inline]
+    fn write_array_unchecked(index: usize, value: u32) {
+        unsafe { mem::write(Self::ADDRS[index], value) };
+    }
+}
+
+pub trait ReadArray<const COUNT: usize>: RegisterArray<COUNT> {
+    #[inline]
+    fn read_array(index: usize) -> Result<u32, Error> {
+        if index >= COUNT {
+            return Err(ErrorKind::BadIndex.into());
+        }
+        Ok(Self::read_array_unchecked(index))
+    }
+    /// Load element `index`, panicking if it is out of range
+    #[inline]
+    fn read_array_unchecked(index: usize) -> u32 {
+        unsafe { mem::read(Self::ADDRS[index]) }
+    }
+}
+
+/// Registers of the GPIO ports, private so that the ports and pins are only
+/// accessed through their owners
+mod port {
+    use crate::{Read, ReadArray, Register, RegisterArray, Write, WriteArray, P0, P1};
+
+    micro_macro::reg! {OUT, ReadWrite, 0x504, instances = [P0, P1]}
+    micro_macro::reg! {OUTSET, ReadWrite, 0x508, instances = [P0, P1]}
+    micro_macro::reg! {OUTCLR, ReadWrite, 0x50C, instances = [P0, P1]}
+    micro_macro::reg! {IN, Read, 0x510, instances = [P0, P1]}
+
+    //"Direction of GPIO pins"
+    micro_macro::reg! {DIR, ReadWrite, 0x514, instances = [P0, P1]}
+
+    //"Set direction of GPIO pins"
+    micro_macro::reg! {DIRSET, ReadWrite, 0x518, instances = [P0, P1]}
+
+    //"Clear direction of GPIO pins"
+    micro_macro::reg! {DIRCLR, ReadWrite, 0x51C, instances = [P0, P1]}
+    //"Latch register indicating what GPIO pins that have met the criteria set in the [PIN_CNF[n]]. SENSE registers"
+    micro_macro::reg! {LATCH, ReadWrite, 0x520, instances = [P0, P1]}
+
+    //"Select between default DETECT signal behavior and LDETECT mode"
+    micro_macro::reg! {DETECTMODE, ReadWrite, 0x524, instances = [P0, P1], fields {
+        detectmode: 0 { default = 0, ldetect = 1 },
+    }}
+    //"Configuration of GPIO pins"
+    micro_macro::reg_array! {PINCNF, ReadWrite, 0x700, count = 32, instances = [P0, P1], fields {
+        dir: 0 { input = 0, output = 1 },
+        input: 1 { connect = 0, disconnect = 1 },
+        pull: 2..=3 { disabled = 0, pulldown = 1, pullup = 3 },
+        drive: 8..=10 {
+            s0s1 = 0, h0s1 = 1, s0h1 = 2, h0h1 = 3, d0s1 = 4, d0h1 = 5, s0d1 = 6, h0d1 = 7,
+        },
+        sense: 16..=17 { disabled = 0, high = 2, low = 3 },
+    }}
+}
+pub(crate) use port::*;
+/// Drive configuration of an output, as `PIN_CNF.DRIVE`
+///
+/// The letters give the drive when low and when high: `S` standard, `H` high
+/// drive, `D` disconnected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Drive {
+    /// Standard 0, standard 1
+    S0S1,
+    /// High drive 0, standard 1
+    H0S1,
+    /// Standard 0, high drive 1
+    S0H1,
+    /// High drive 0, high drive 1
+    H0H1,
+    /// Disconnect 0, standard 1, i.e. open source
+    D0S1,
+    /// Disconnect 0, high drive 1
+    D0H1,
+    /// Standard 0, disconnect 1, i.e. open drain
+    S0D1,
+    /// High drive 0, disconnect 1
+    H0D1,
+}
+impl From<Drive> for pincnf::DRIVE_A {
+    #[inline]
+    fn from(drive: Drive) -> Self {
+        match drive {
+            Drive::S0S1 => pincnf::DRIVE_A::S0s1,
+            Drive::H0S1 => pincnf::DRIVE_A::H0s1,
+            Drive::S0H1 => pincnf::DRIVE_A::S0h1,
+            Drive::H0H1 => pincnf::DRIVE_A::H0h1,
+            Drive::D0S1 => pincnf::DRIVE_A::D0s1,
+            Drive::D0H1 => pincnf::DRIVE_A::D0h1,
+            Drive::S0D1 => pincnf::DRIVE_A::S0d1,
+            Drive::H0D1 => pincnf::DRIVE_A::H0d1,
+        }
+    }
+}
+impl From<pincnf::DRIVE_A> for Drive {
+    #[inline]
+    fn from(drive: pincnf::DRIVE_A) -> Self {
+        match drive {
+            pincnf::DRIVE_A::S0s1 => Drive::S0S1,
+            pincnf::DRIVE_A::H0s1 => Drive::H0S1,
+            pincnf::DRIVE_A::S0h1 => Drive::S0H1,
+            pincnf::DRIVE_A::H0h1 => Drive::H0H1,
+            pincnf::DRIVE_A::D0s1 => Drive::D0S1,
+            pincnf::DRIVE_A::D0h1 => Drive::D0H1,
+            pincnf::DRIVE_A::S0d1 => Drive::S0D1,
+            pincnf::DRIVE_A::H0d1 => Drive::H0D1,
+        }
+    }
+}
+/// How the `DETECT` signal of a port follows the pins with `SENSE` set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetectMode {
+    /// `DETECT` is high while any sensed pin is at its sense level
+    Default,
+    /// `DETECT` is high while any bit of `LATCH` is set, so a pin that has
+    /// met its sense level keeps it high until the latch is cleared
+    Latched,
+}
+/// Registers shared by all pins of a GPIO port
+///
+/// Only the crate calls these, for drivers that own several pins of a port.
+/// Everyone else goes through the owned [`P0`] and [`P1`] before they are
+/// split, and the registers themselves are private, so nothing can touch
+/// pins behind the back of their handles:
+///
+/// ```compile_fail,E0061
+/// use microrust::{PinMask, P0};
+///
+/// P0::set_low_masked(PinMask::pin(21));
+/// ```
+///
+/// ```compile_fail,E0603
+/// use microrust::{PinMask, Write, OUTCLR0};
+///
+/// OUTCLR0::write_raw(1 << 21);
+/// ```
+///
+/// The masked operations update every pin whose bit is set in `mask` with a
+/// single store to a write-1-to-set or write-1-to-clear register, so pins
+/// outside the mask are never touched.
+pub(crate) trait PortRegisters: Port {
+    type OUT: OUT + Register<Instance = Self>;
+    type OUTSET: OUTSET + Register<Instance = Self>;
+    type OUTCLR: OUTCLR + Register<Instance = Self>;
+    type IN: IN + Register<Instance = Self>;
+    type DIR: DIR + Register<Instance = Self>;
+    type DIRSET: DIRSET + Register<Instance = Self>;
+    type DIRCLR: DIRCLR + Register<Instance = Self>;
+    type LATCH: LATCH + Register<Instance = Self>;
+    type DETECTMODE: DETECTMODE + Register<Instance = Self>;
+    type PINCNF: PINCNF + RegisterArray<32, Instance = Self>;
+    /// Drive the pins in `mask` high
+    #[inline]
+    fn set_high_masked(mask: PinMask) {
+        Self::OUTSET::write_pins(mask)
+    }
+    /// Drive the pins in `mask` low
+    #[inline]
+    fn set_low_masked(mask: PinMask) {
+        Self::OUTCLR::write_pins(mask)
+    }
+    /// Drive the pins in `mask` high if they are in `high`, low otherwise
+    #[inline]
+    fn write_masked(mask: PinMask, high: PinMask) {
+        Self::set_high_masked(mask & high);
+        Self::set_low_masked(mask - high);
+    }
+    /// Flip the level the pins in `mask` are driven to
+    ///
+    /// Reading `OUT` and writing `OUTSET` and `OUTCLR` run in a critical
+    /// section, otherwise an interrupt driving pins of `mask` in between
+    /// would be overwritten with the levels read before it.
+    #[inline]
+    fn toggle_masked(mask: PinMask) {
+        critical_section::with(|_| Self::write_masked(mask, !Self::OUT::read_pins()))
+    }
+    /// Make the pins in `mask` outputs
+    #[inline]
+    fn make_output_masked(mask: PinMask) {
+        Self::DIRSET::write_pins(mask)
+    }
+    /// Make the pins in `mask` inputs
+    #[inline]
+    fn make_input_masked(mask: PinMask) {
+        Self::DIRCLR::write_pins(mask)
+    }
+    /// Pins that read high
+    #[inline]
+    fn high_pins() -> PinMask {
+        Self::IN::read_pins()
+    }
+    /// Pins that have met their sense level since their latch was cleared
+    #[inline]
+    fn latched_pins() -> PinMask {
+        Self::LATCH::read_pins()
+    }
+    /// Clear the latch of the pins in `mask`
+    ///
+    /// A latch that is cleared while its pin is still at the sense level is
+    /// set again right away.
+    #[inline]
+    fn clear_latch(mask: PinMask) {
+        Self::LATCH::write_pins(mask)
+    }
+    /// Select how the `DETECT` signal of the port is generated
+    #[inline]
+    fn set_detect_mode(mode: DetectMode) {
+        Self::DETECTMODE::write(|w| match mode {
+            DetectMode::Default => w.detectmode().default(),
+            DetectMode::Latched => w.detectmode().ldetect(),
+        })
+    }
+    /// How the `DETECT` signal of the port is generated
+    #[inline]
+    fn detect_mode() -> DetectMode {
+        if Self::DETECTMODE::read().detectmode().is_ldetect() {
+            DetectMode::Latched
+        } else {
+            DetectMode::Default
+        }
+    }
+}
+/// One of the pins of the GPIO ports
+///
+/// Only the numbers of a pin are public. Its registers are accessed through
+/// the [`Handle`](gpio::Handle) that owns it, so nothing can drive or
+/// reconfigure the pin behind the back of its owner:
+///
+/// ```compile_fail,E0599
+/// use microrust::{Pin, P021};
+///
+/// P021::set_low();
+/// ```
+#[allow(private_bounds)]
+pub trait Pin: PinBanks {
+    const PIN_ID: usize;
+    /// The pin as a mask of its port
+    const PIN_MASK: PinMask = PinMask::pin(Self::PIN_ID);
+    /// Number of the port the pin belongs to, as used in `PSEL` registers
+    const PORT_ID: usize;
+}
+/// Registers of the port of a pin, which also seals [`Pin`]
+pub(crate) trait PinBanks {
+    type Port: Port;
+    type OUT: OUT + Register<Instance = Self::Port>;
+    type OUTSET: OUTSET + Register<Instance = Self::Port>;
+    type OUTCLR: OUTCLR + Register<Instance = Self::Port>;
+    type IN: IN + Register<Instance = Self::Port>;
+    type DIR: DIR + Register<Instance = Self::Port>;
+    type DIRSET: DIRSET + Register<Instance = Self::Port>;
+    type DIRCLR: DIRCLR + Register<Instance = Self::Port>;
+    type LATCH: LATCH + Register<Instance = Self::Port>;
+    type DETECTMODE: DETECTMODE + Register<Instance = Self::Port>;
+    type PINCNF: PINCNF + RegisterArray<32, Instance = Self::Port>;
+}
+/// Register-level operations on a single pin, for the handles and drivers of
+/// this crate
+pub(crate) trait PinRegisters: Pin {
+    /// Read GPIO OUT register for pin
+    fn read_out() -> Level {
+        Self::OUT::read_pin_unchecked(Self::PIN_ID)
+    }
+    /// Write GPIO OUT register for pin
+    fn write_out(value: Level) {
+        match value {
+            Level::High => Self::set_high(),
+            Level::Low => Self::set_low(),
+        }
+    }
+    /// Drive the pin high through OUTSET, leaving the other pins untouched
+    #[inline]
+    fn set_high() {
+        Self::OUTSET::write_pins(Self::PIN_MASK)
+    }
+    /// Drive the pin low through OUTCLR, leaving the other pins untouched
+    #[inline]
+    fn set_low() {
+        Self::OUTCLR::write_pins(Self::PIN_MASK)
+    }
+    /// Flip the level the pin is driven to
+    ///
+    /// Reading `OUT` and writing `OUTSET` or `OUTCLR` run in a critical
+    /// section, otherwise an interrupt driving the pin in between would be
+    /// overwritten with the level read before it.
+    #[inline]
+    fn toggle() {
+        critical_section::with(|_| {
+            if Self::read_out().is_low() {
+                Self::set_high()
+            } else {
+                Self::set_low()
+            }
+        })
+    }
+    /// Read GPIO IN register for pin
+    fn read_in() -> Level {
+        Self::IN::read_pin_unchecked(Self::PIN_ID)
+    }
+    /// Make the pin an output through DIRSET, leaving the other pins untouched
+    #[inline]
+    fn make_output() {
+        Self::DIRSET::write_pins(Self::PIN_MASK)
+    }
+    /// Make the pin an input through DIRCLR, leaving the other pins untouched
+    #[inline]
+    fn make_input() {
+        Self::DIRCLR::write_pins(Self::PIN_MASK)
+    }
+    /// Set the drive configuration of the pin, leaving the rest of `PIN_CNF`
+    fn set_drive(drive: Drive) {
+        Self::PINCNF::modify(Self::PIN_ID, |_, w| w.drive().variant(drive.into()));
+    }
+    /// The drive configuration of the pin
+    fn drive() -> Drive {
+        // All eight values of the three bit field are known ones
+        match Self::PINCNF::read(Self::PIN_ID).drive().variant() {
+            Some(drive) => drive.into(),
+            None => unreachable!(),
+        }
+    }
+    /// Raise `DETECT` and set the latch of the pin while it is high
+    ///
+    /// This is also what wakes the chip from System OFF.
+    fn sense_high() {
+        Self::PINCNF::modify(Self::PIN_ID, |_, w| w.sense().high());
+    }
+    /// Raise `DETECT` and set the latch of the pin while it is low
+    fn sense_low() {
+        Self::PINCNF::modify(Self::PIN_ID, |_, w| w.sense().low());
+    }
+    /// Stop sensing the pin
+    fn sense_disable() {
+        Self::PINCNF::modify(Self::PIN_ID, |_, w| w.sense().disabled());
+    }
+}
+impl<P: Pin> PinRegisters for P {}
+macro_rules! __ {
+    ($name:ident, $port_number:literal) => {
+        paste::paste! {
+            type $name = [<$name $port_number>];
+        }
+    };
+}
+macro_rules! __def__ {
+    ($port_number:literal) => {
+        __!(OUT, $port_number);
+        __!(OUTSET, $port_number);
+        __!(OUTCLR, $port_number);
+        __!(IN, $port_number);
+        __!(DIR, $port_number);
+        __!(DIRSET, $port_number);
+        __!(DIRCLR, $port_number);
+        __!(LATCH, $port_number);
+        __!(DETECTMODE, $port_number);
+        __!(PINCNF, $port_number);
+    };
+}
+/// Fails to compile unless pin `$pin` is a pin of `$port`, so `def_pin!`
+/// can't define pins the port doesn't have
+///
+/// ```
+/// microrust::__assert_pin__!(P1, 09);
+/// ```
+///
+/// ```compile_fail,E0080
+/// microrust::__assert_pin__!(P1, 10);
+/// ```
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_pin__ {
+    ($port:ident, $pin:literal) => {
+        #[allow(clippy::zero_prefixed_literal, unused_comparisons)]
+        const _: () = assert!(
+            $pin <= <$crate::$port as $crate::Port>::RANGE.end as usize,
+            concat!(
+                stringify!($port),
+                stringify!($pin),
+                " is not a pin of ",
+                stringify!($port)
+            ),
+        );
+    };
+}
+macro_rules! def_pin {
+    (0, $($pin_number:literal),+ $(,)?) => {
+        // $($n, $name)*
+        $(paste::paste! {
+            #[non_exhaustive]
+            pub struct [<P 0 $pin_number>];
+            impl crate::Pin for [<P 0 $pin_number>] {
+                #[allow(clippy::zero_prefixed_literal)]
+                const PIN_ID: usize = $pin_number;
+                const PORT_ID: usize = 0;
+            }
+            impl crate::PinBanks for [<P 0 $pin_number>] {
+                type Port = P0;
+                __def__!(0);
+            }
+            crate::__assert_pin__!(P0, $pin_number);
+        })*
+        impl crate::PortRegisters for P0 {
+            __def__!(0);
+        }
+        def_parts!(0, $($pin_number),+);
+    };
+    ($port_number:literal, $($pin_number:literal),+ $(,)?) => {
+        // $($n, $name)*
+        $(paste::paste! {
+            #[non_exhaustive]
+            pub struct [<P $port_number $pin_number>];
+            impl crate::Pin for [<P $port_number $pin_number>] {
+                #[allow(clippy::zero_prefixed_literal)]
+                const PIN_ID: usize = $pin_number;
+                const PORT_ID: usize = $port_number;
+            }
+            impl crate::PinBanks for [<P $port_number $pin_number>] {
+                type Port = [<P $port_number>];
+                __!(OUT, $port_number);
+                __!(OUTSET, $port_number);
+                __!(OUTCLR, $port_number);
+                __!(IN, $port_number);
+                __!(DIR, $port_number);
+                __!(DIRSET, $port_number);
+                __!(DIRCLR, $port_number);
+                __!(LATCH, $port_number);
+                __!(DETECTMODE, $port_number);
+                __!(PINCNF, $port_number);
+            }
+            crate::__assert_pin__!([<P $port_number>], $pin_number);
+        })*
+        paste::paste! {
+            impl crate::PortRegisters for [<P $port_number>] {
+                __def__!($port_number);
+            }
+        }
+        def_parts!($port_number, $($pin_number),+);
+    };
+}
+macro_rules! def_parts {
+    ($port_number:literal, $($pin_number:literal),+) => {
+        paste::paste! {
+            #[doc = "Handles to the pins of [`P" $port_number "`], from [`P" $port_number "::split`]"]
+            pub struct [<Parts $port_number>] {
+                $(pub [<p $port_number _ $pin_number>]:
+                    crate::gpio::Handle<[<P $port_number $pin_number>], crate::gpio::Disconnected>,)+
+            }
+            impl [<P $port_number>] {
+                /// Split the port into owned handles to each of its pins
+                ///
+                /// Every pin is put back into its reset configuration.
+                pub fn split(self) -> [<Parts $port_number>] {
+                    [<Parts $port_number>] {
+                        $([<p $port_number _ $pin_number>]:
+                            crate::gpio::Handle::new([<P $port_number $pin_number>]),)+
+                    }
+                }
+                /// Drive the pins in `mask` high
+                #[inline]
+                pub fn set_high_masked(&mut self, mask: crate::PinMask) {
+                    <Self as crate::PortRegisters>::set_high_masked(mask)
+                }
+                /// Drive the pins in `mask` low
+                #[inline]
+                pub fn set_low_masked(&mut self, mask: crate::PinMask) {
+                    <Self as crate::PortRegisters>::set_low_masked(mask)
+                }
+                /// Drive the pins in `mask` high if they are in `high`, low otherwise
+                #[inline]
+                pub fn write_masked(&mut self, mask: crate::PinMask, high: crate::PinMask) {
+                    <Self as crate::PortRegisters>::write_masked(mask, high)
+                }
+                /// Flip the level the pins in `mask` are driven to
+                #[inline]
+                pub fn toggle_masked(&mut self, mask: crate::PinMask) {
+                    <Self as crate::PortRegisters>::toggle_masked(mask)
+                }
+                /// Make the pins in `mask` outputs
+                #[inline]
+                pub fn make_output_masked(&mut self, mask: crate::PinMask) {
+                    <Self as crate::PortRegisters>::make_output_masked(mask)
+                }
+                /// Make the pins in `mask` inputs
+                #[inline]
+                pub fn make_input_masked(&mut self, mask: crate::PinMask) {
+                    <Self as crate::PortRegisters>::make_input_masked(mask)
+                }
+                /// Pins that read high
+                #[inline]
+                pub fn high_pins(&self) -> crate::PinMask {
+                    <Self as crate::PortRegisters>::high_pins()
+                }
+                /// Pins that have met their sense level since their latch was cleared
+                #[inline]
+                pub fn latched_pins(&self) -> crate::PinMask {
+                    <Self as crate::PortRegisters>::latched_pins()
+                }
+                /// Clear the latch of the pins in `mask`
+                #[inline]
+                pub fn clear_latch(&mut self, mask: crate::PinMask) {
+                    <Self as crate::PortRegisters>::clear_latch(mask)
+                }
+                /// Select how the `DETECT` signal of the port is generated
+                #[inline]
+                pub fn set_detect_mode(&mut self, mode: crate::DetectMode) {
+                    <Self as crate::PortRegisters>::set_detect_mode(mode)
+                }
+                /// How the `DETECT` signal of the port is generated
+                #[inline]
+                pub fn detect_mode(&self) -> crate::DetectMode {
+                    <Self as crate::PortRegisters>::detect_mode()
+                }
+            }
+        }
+    };
+}
+use micro_macro::{address, port};
+/// General purpose input and output port
+/// P0.00 to P0.31 implemented
+#[address(0x5000_0000)]
+#[port(..=31)]
+#[non_exhaustive]
+pub struct P0;
+def_pin!(
+    0, 00, 01, 02, 03, 04, 05, 06, 07, 08, 09, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22,
+    23, 24, 25, 26, 27, 28, 29, 30, 31
+);
+/// General purpose input and output port
+/// P1.00 to P1.09 implemented
+#[non_exhaustive]
+pub struct P1;
+impl Addressable for P1 {
+    const ADDR: usize = 0x5000_0300;
+}
+impl Port for P1 {
+    const RANGE: RangeToInclusive<u8> = ..=9;
+}
+def_pin!(1, 00, 01, 02, 03, 04, 05, 06, 07, 08, 09);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::sim;
+
+    #[test]
+    fn write_flips_only_the_requested_bit() {
+        sim::reset();
+        sim::poke(DIR0::ADDR, 0b1000);
+        DIR0::write_pin(1, Level::High).unwrap();
+        assert_eq!(sim::writes(), [(0x5000_0514, 0b1010)]);
+        DIR0::write_pin(1, Level::Low).unwrap();
+        assert_eq!(sim::peek(DIR0::ADDR), 0b1000);
+    }
+
+    #[test]
+    fn write_skips_store_when_bit_already_matches() {
+        sim::reset();
+        sim::poke(DIR1::ADDR, 0b0010);
+        DIR1::write_pin(1, Level::High).unwrap();
+        assert!(sim::writes().is_empty());
+    }
+
+    #[test]
+    fn write_rejects_pin_outside_port() {
+        sim::reset();
+        assert!(DIR1::write_pin(12, Level::High).is_err());
+        assert!(sim::writes().is_empty());
+    }
+
+    #[test]
+    fn last_pin_of_port_is_valid() {
+        sim::reset();
+        DIR1::write_pin(9, Level::High).unwrap();
+        DIR0::write_pin(31, Level::High).unwrap();
+        assert_eq!(sim::peek(DIR1::ADDR), 1 << 9);
+        assert_eq!(sim::peek(DIR0::ADDR), 1 << 31);
+    }
+
+    #[test]
+    fn read_pin_reads_the_bit_of_the_pin() {
+        sim::reset();
+        sim::poke(IN0::ADDR, 1 << 3);
+        assert_eq!(IN0::read_pin(3), Ok(Level::High));
+        assert_eq!(IN0::read_pin(2), Ok(Level::Low));
+        assert_eq!(IN0::read_pin(1), Ok(Level::Low));
+        assert_eq!(P003::read_in(), Level::High);
+        assert_eq!(IN0::read_pins(), PinMask::pin(3));
+    }
+
+    #[test]
+    fn pin_masks_hold_only_their_pin() {
+        assert_eq!(P000::PIN_MASK, PinMask::new(1));
+        assert_eq!(P014::PIN_MASK.bits(), 1 << 14);
+        assert_eq!(P031::PIN_MASK.iter().collect::<Vec<_>>(), [31]);
+        assert_eq!(P109::PIN_MASK, PinMask::pin(9));
+    }
+
+    #[test]
+    fn single_pin_ops_store_only_their_bit() {
+        sim::reset();
+        sim::poke(OUT0::ADDR, 0b0001);
+        P003::set_high();
+        P003::make_output();
+        P000::toggle();
+        assert_eq!(
+            sim::writes(),
+            [
+                (0x5000_0508, 1 << 3),
+                (0x5000_0518, 1 << 3),
+                (0x5000_050C, 1)
+            ]
+        );
+        assert_eq!(sim::peek(OUT0::ADDR), 1 << 3);
+    }
+
+    #[test]
+    fn masked_port_ops_update_many_pins_per_store() {
+        sim::reset();
+        sim::poke(OUT1::ADDR, 0b0011);
+        let mut p1 = unsafe { Peripherals::steal() }.p1;
+        p1.write_masked(PinMask::from_pins(&[1, 2]), PinMask::pin(2));
+        assert_eq!(sim::peek(OUT1::ADDR), 0b0101);
+        p1.toggle_masked(PinMask::from_pins(&[2, 3]));
+        assert_eq!(sim::peek(OUT1::ADDR), 0b1001);
+        p1.make_output_masked(PinMask::new(0b1111));
+        p1.make_input_masked(PinMask::from_pins(&[0, 2]));
+        assert_eq!(sim::peek(DIR1::ADDR), 0b1010);
+    }
+
+    #[test]
+    fn typed_accessors_touch_only_their_field() {
+        sim::reset();
+        PINCNF0::modify(7, |_, w| w.drive().h0h1().sense().low());
+        let cnf = PINCNF0::read(7);
+        assert_eq!(cnf.bits(), 0x0003_0302);
+        assert!(cnf.input().is_disconnect());
+        assert_eq!(cnf.drive().variant(), Some(pincnf::DRIVE_A::H0h1));
+        DETECTMODE1::write(|w| w.detectmode().ldetect());
+        assert_eq!(sim::writes()[1], (0x5000_0824, 1));
+        assert!(DETECTMODE1::read().detectmode().is_ldetect());
+    }
+
+    #[test]
+    fn sense_and_latch() {
+        sim::reset();
+        let mut p1 = unsafe { Peripherals::steal() }.p1;
+        p1.set_detect_mode(DetectMode::Latched);
+        let mut pin = gpio::Handle::new(P104).into_pull_up_input();
+        pin.sense(Level::Low);
+        assert_eq!(sim::peek(0x5000_0A10), 0x0003_000C);
+        pin.sense_disable();
+        assert_eq!(sim::peek(0x5000_0A10), 0b1100);
+        assert_eq!(sim::peek(DETECTMODE1::ADDR), 1);
+        assert_eq!(p1.detect_mode(), DetectMode::Latched);
+        sim::poke(LATCH1::ADDR, 0b1_0010);
+        assert_eq!(p1.latched_pins(), PinMask::from_pins(&[1, 4]));
+        assert!(pin.is_latched());
+        pin.clear_latch();
+        assert_eq!(sim::writes().last(), Some(&(LATCH1::ADDR, 1 << 4)));
+    }
+
+    #[test]
+    fn set_drive_keeps_the_rest_of_pincnf() {
+        sim::reset();
+        let mut pin = gpio::Handle::new(P011).into_push_pull_output(Level::Low);
+        // A pull and sense level left over from an earlier configuration
+        sim::poke(0x5000_0700 + 11 * 4, 0x0003_000F);
+        pin.set_drive(Drive::H0D1);
+        assert_eq!(sim::peek(0x5000_0700 + 11 * 4), 0x0003_070F);
+        assert_eq!(pin.drive(), Drive::H0D1);
+        for drive in [
+            Drive::S0H1,
+            Drive::H0S1,
+            Drive::D0S1,
+            Drive::D0H1,
+            Drive::S0S1,
+        ] {
+            pin.set_drive(drive);
+            assert_eq!(pin.drive(), drive);
+        }
+        assert_eq!(sim::peek(0x5000_0700 + 11 * 4), 0x0003_000F);
+    }
+
+    #[allow(dead_code)]
+    mod uarte {
+        use crate::{Addressable, Read, Register, Write};
+
+        pub struct UARTE0;
+        impl Addressable for UARTE0 {
+            const ADDR: usize = 0x4000_2000;
+        }
+        pub struct UARTE1;
+        impl Addressable for UARTE1 {
+            const ADDR: usize = 0x4002_8000;
+        }
+        micro_macro::reg! {ENABLE, ReadWrite, 0x500, instances = [UARTE0, UARTE1]}
+    }
+
+    #[test]
+    fn banks_follow_the_instance_list() {
+        assert_eq!(uarte::ENABLE0::ADDR, 0x4000_2500);
+        assert_eq!(uarte::ENABLE1::ADDR, 0x4002_8500);
+        sim::reset();
+        uarte::ENABLE1::write_raw(8);
+        assert_eq!(sim::writes(), [(0x4002_8500, 8)]);
+    }
+
+    #[allow(dead_code, clippy::upper_case_acronyms)]
+    mod ppi {
+        use crate::{Addressable, ReadArray, RegisterArray, WriteArray};
+
+        pub struct PPI;
+        impl Addressable for PPI {
+            const ADDR: usize = 0x4001_F000;
+        }
+        micro_macro::reg_array! {CH_EEP, ReadWrite, 0x510, count = 20, stride = 8, instances = [PPI]}
+    }
+
+    #[test]
+    fn array_elements_are_stride_apart() {
+        use crate::RegisterArray;
+
+        assert_eq!(ppi::CH_EEP0::ADDRS[0], 0x4001_F510);
+        assert_eq!(ppi::CH_EEP0::ADDRS[19], 0x4001_F5A8);
+        assert_eq!(ppi::CH_EEP0[1], 0x4001_F518);
+    }
+
+    #[test]
+    fn array_accesses_are_bounds_checked() {
+        sim::reset();
+        assert_eq!(PINCNF1::read_array(32), Err(ErrorKind::BadIndex.into()));
+        assert_eq!(
+            PINCNF1::write_array(32, 0).unwrap_err().kind(),
+            ErrorKind::BadIndex
+        );
+        assert!(sim::writes().is_empty());
+        PINCNF1::write_array(31, 0).unwrap();
+        assert_eq!(sim::writes(), [(PINCNF1[31], 0)]);
+    }
+
+    #[allow(dead_code)]
+    mod svd {
+        use crate::{
+            Addressable, Port, Read, ReadArray, Register, RegisterArray, Write, WriteArray,
+        };
+        micro_macro::svd! { "svd/nrf52833.svd", P0 { port = ..=31 }, P1 { port = ..=9 } }
+    }
+
+    #[test]
+    fn svd_registers_match_hand_written_ones() {
+        use svd::PIN_CNF;
+
+        assert_eq!(svd::OUT0::ADDR, OUT0::ADDR);
+        assert_eq!(svd::IN1::ADDR, IN1::ADDR);
+        assert_eq!(svd::DETECTMODE1::ADDR, DETECTMODE1::ADDR);
+        assert_eq!(svd::PIN_CNF0::ADDRS, PINCNF0::ADDRS);
+        assert_eq!(svd::PIN_CNF1::ADDRS, PINCNF1::ADDRS);
+        sim::reset();
+        svd::PIN_CNF1::modify(3, |_, w| w.pull().pullup().sense().high());
+        assert_eq!(sim::peek(PINCNF1::ADDRS[3]), 0x0002_000E);
+        assert!(PINCNF1::read(3).sense().is_high());
+    }
+}
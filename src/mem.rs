@@ -0,0 +1,81 @@
+//! Memory access backend sitting underneath the register traits.
+//!
+//! Every register access in the crate goes through [`read()`] and [`write()`],
+//! which forward to the [`Backend`] selected for the current target. On the
+//! nRF52833 itself that is [`Volatile`], which touches the memory mapped
+//! peripherals directly. Everywhere else it is [`sim::Simulated`], a register
+//! file modelled after the nRF52833 so the register traits can be exercised
+//! by `cargo test` on the host.
+
+/// A way of reading and writing 32-bit peripheral registers
+pub trait Backend {
+    /// Read the register at `addr`
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be the address of a readable peripheral register.
+    unsafe fn read(addr: usize) -> u32;
+    /// Write `value` to the register at `addr`
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be the address of a writable peripheral register.
+    unsafe fn write(addr: usize, value: u32);
+}
+
+/// Volatile accesses straight to the memory mapped peripherals
+pub struct Volatile;
+impl Backend for Volatile {
+    #[inline]
+    unsafe fn read(addr: usize) -> u32 {
+        unsafe { core::ptr::read_volatile(addr as *const u32) }
+    }
+    #[inline]
+    unsafe fn write(addr: usize, value: u32) {
+        unsafe { core::ptr::write_volatile(addr as *mut u32, value) }
+    }
+}
+
+#[cfg(not(target_os = "none"))]
+pub mod sim;
+
+/// The backend used by the register traits on this target
+#[cfg(target_os = "none")]
+pub type Selected = Volatile;
+/// The backend used by the register traits on this target
+#[cfg(not(target_os = "none"))]
+pub type Selected = sim::Simulated;
+
+/// Read the register at `addr` through the [`Selected`] backend
+///
+/// # Safety
+///
+/// See [`Backend::read`].
+#[inline]
+pub unsafe fn read(addr: usize) -> u32 {
+    unsafe { <Selected as Backend>::read(addr) }
+}
+/// Write `value` to the register at `addr` through the [`Selected`] backend
+///
+/// # Safety
+///
+/// See [`Backend::write`].
+#[inline]
+pub unsafe fn write(addr: usize, value: u32) {
+    unsafe { <Selected as Backend>::write(addr, value) }
+}
+
+/// Addresses of the data RAM, the only memory EasyDMA can reach
+pub const RAM: core::ops::Range<usize> = 0x2000_0000..0x2002_0000;
+/// Whether EasyDMA can reach all of `bytes`
+#[cfg(target_os = "none")]
+pub fn in_ram(bytes: &[u8]) -> bool {
+    let range = bytes.as_ptr_range();
+    RAM.contains(&(range.start as usize)) && range.end as usize <= RAM.end
+}
+/// Whether EasyDMA can reach all of `bytes`; the simulated peripherals reach
+/// all of host memory
+#[cfg(not(target_os = "none"))]
+pub fn in_ram(_bytes: &[u8]) -> bool {
+    true
+}
@@ -0,0 +1,183 @@
+//! Simulated nRF52833 register file for host builds.
+//!
+//! Registers start out at their reset values and hold whatever was last
+//! written to them, with the side effects of the GPIO write-1-to-set and
+//! write-1-to-clear registers modelled so that e.g. a write to `OUTSET` shows
+//! up in `OUT`. Every write made through the backend is logged, so a test can
+//! check exactly which stores a register trait performed.
+//!
+//! The register file is thread local, and the test harness runs every test on
+//! its own thread, so tests do not observe each other's writes.
+extern crate std;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::vec::Vec;
+
+use super::Backend;
+
+/// Base addresses of the GPIO ports
+const GPIO_PORTS: [usize; 2] = [0x5000_0000, 0x5000_0300];
+const OUT: usize = 0x504;
+const OUTSET: usize = 0x508;
+const OUTCLR: usize = 0x50C;
+const DIR: usize = 0x514;
+const DIRSET: usize = 0x518;
+const DIRCLR: usize = 0x51C;
+const LATCH: usize = 0x520;
+const PINCNF: usize = 0x700;
+const PINCNF_COUNT: usize = 32;
+/// Reset value of `PIN_CNF[n]`, input with the buffer disconnected
+const PINCNF_RESET: u32 = 0x0000_0002;
+
+/// How a store to a simulated register affects the register file
+enum Effect {
+    /// The register holds the written value
+    Store,
+    /// Bits written as one are set in the register at the given address
+    Set(usize),
+    /// Bits written as one are cleared in the register at the given address
+    Clear(usize),
+}
+fn gpio_register(addr: usize) -> Option<(usize, usize)> {
+    GPIO_PORTS
+        .iter()
+        .find(|base| (**base + OUT..**base + PINCNF + PINCNF_COUNT * 4).contains(&addr))
+        .map(|base| (*base, addr - base))
+}
+/// The register a read of `addr` is served from
+fn alias(addr: usize) -> usize {
+    match gpio_register(addr) {
+        Some((base, OUTSET | OUTCLR)) => base + OUT,
+        Some((base, DIRSET | DIRCLR)) => base + DIR,
+        _ => addr,
+    }
+}
+fn effect(addr: usize) -> Effect {
+    match gpio_register(addr) {
+        Some((base, OUTSET)) => Effect::Set(base + OUT),
+        Some((base, OUTCLR)) => Effect::Clear(base + OUT),
+        Some((base, DIRSET)) => Effect::Set(base + DIR),
+        Some((base, DIRCLR)) => Effect::Clear(base + DIR),
+        Some((_, LATCH)) => Effect::Clear(addr),
+        _ => Effect::Store,
+    }
+}
+fn reset_value(addr: usize) -> u32 {
+    match gpio_register(addr) {
+        Some((_, offset)) if (PINCNF..PINCNF + PINCNF_COUNT * 4).contains(&offset) => PINCNF_RESET,
+        _ => 0,
+    }
+}
+
+#[derive(Default)]
+struct RegisterFile {
+    values: HashMap<usize, u32>,
+    writes: Vec<(usize, u32)>,
+}
+impl RegisterFile {
+    fn get(&self, addr: usize) -> u32 {
+        self.values
+            .get(&addr)
+            .copied()
+            .unwrap_or_else(|| reset_value(addr))
+    }
+    fn store(&mut self, addr: usize, value: u32) {
+        self.writes.push((addr, value));
+        match effect(addr) {
+            Effect::Store => {
+                self.values.insert(addr, value);
+            }
+            Effect::Set(target) => {
+                let current = self.get(target);
+                self.values.insert(target, current | value);
+            }
+            Effect::Clear(target) => {
+                let current = self.get(target);
+                self.values.insert(target, current & !value);
+            }
+        }
+    }
+}
+
+std::thread_local! {
+    static REGISTERS: RefCell<RegisterFile> = RefCell::new(RegisterFile::default());
+}
+
+/// Backend serving register accesses from the simulated register file
+pub struct Simulated;
+impl Backend for Simulated {
+    unsafe fn read(addr: usize) -> u32 {
+        peek(addr)
+    }
+    unsafe fn write(addr: usize, value: u32) {
+        REGISTERS.with_borrow_mut(|registers| registers.store(addr, value))
+    }
+}
+
+/// Put every register back to its reset value and forget all logged writes
+pub fn reset() {
+    REGISTERS.with_borrow_mut(|registers| *registers = RegisterFile::default())
+}
+/// Current value of the register at `addr`
+pub fn peek(addr: usize) -> u32 {
+    REGISTERS.with_borrow(|registers| registers.get(alias(addr)))
+}
+/// Set the register at `addr` to `value` without logging a write or applying
+/// side effects, e.g. to drive the level seen through `IN`
+pub fn poke(addr: usize, value: u32) {
+    REGISTERS.with_borrow_mut(|registers| {
+        registers.values.insert(addr, value);
+    })
+}
+/// Every `(address, value)` written through the backend since the last
+/// [`reset`], oldest first
+pub fn writes() -> Vec<(usize, u32)> {
+    REGISTERS.with_borrow(|registers| registers.writes.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::{read, write};
+
+    #[test]
+    fn registers_start_at_reset_value() {
+        reset();
+        assert_eq!(peek(0x5000_0504), 0);
+        assert_eq!(peek(0x5000_0700), PINCNF_RESET);
+        assert_eq!(peek(0x5000_0A24), PINCNF_RESET);
+    }
+
+    #[test]
+    fn set_and_clear_registers_update_target() {
+        reset();
+        unsafe {
+            write(0x5000_0508, 0b0110);
+            write(0x5000_050C, 0b0010);
+            write(0x5000_0818, 1 << 3);
+        }
+        assert_eq!(peek(0x5000_0504), 0b0100);
+        assert_eq!(unsafe { read(0x5000_0508) }, 0b0100);
+        assert_eq!(peek(0x5000_0814), 1 << 3);
+        assert_eq!(peek(0x5000_0514), 0);
+    }
+
+    #[test]
+    fn latch_is_write_one_to_clear() {
+        reset();
+        poke(0x5000_0820, 0b1011);
+        unsafe { write(0x5000_0820, 0b0001) };
+        assert_eq!(peek(0x5000_0820), 0b1010);
+    }
+
+    #[test]
+    fn writes_are_logged_in_order() {
+        reset();
+        unsafe {
+            write(0x5000_0514, 1);
+            write(0x5000_0700, 0);
+        }
+        assert_eq!(writes(), [(0x5000_0514, 1), (0x5000_0700, 0)]);
+    }
+}
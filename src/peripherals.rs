@@ -0,0 +1,118 @@
+//! Single point of ownership for the peripherals.
+//!
+//! The port and pin types can't be constructed outside this crate, so the
+//! only way to get hold of one is through [`Peripherals::take`], which hands
+//! every port out exactly once. Pins are then obtained by splitting a port:
+//!
+//! ```compile_fail
+//! let pin = microrust::P014;
+//! ```
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::clock::CLOCK;
+use crate::gpiote::GPIOTE;
+use crate::rtc::{RTC0, RTC1, RTC2};
+use crate::spim::{SPIM2, SPIM3};
+use crate::timer::{TIMER0, TIMER1, TIMER2, TIMER3, TIMER4};
+use crate::twim::{TWIM0, TWIM1};
+use crate::uarte::{UARTE0, UARTE1};
+use crate::{P0, P1};
+
+static TAKEN: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+
+/// All peripherals of the nRF52833 supported by this crate
+pub struct Peripherals {
+    /// GPIO port 0
+    pub p0: P0,
+    /// GPIO port 1
+    pub p1: P1,
+    /// Clock control
+    pub clock: CLOCK,
+    /// GPIO tasks and events
+    pub gpiote: GPIOTE,
+    /// Timer 0, with four capture/compare channels
+    pub timer0: TIMER0,
+    /// Timer 1, with four capture/compare channels
+    pub timer1: TIMER1,
+    /// Timer 2, with four capture/compare channels
+    pub timer2: TIMER2,
+    /// Timer 3, with six capture/compare channels
+    pub timer3: TIMER3,
+    /// Timer 4, with six capture/compare channels
+    pub timer4: TIMER4,
+    /// I2C master 0, or SPIM0 through [`TWIM0::into_spim`]
+    pub twim0: TWIM0,
+    /// I2C master 1, or SPIM1 through [`TWIM1::into_spim`]
+    pub twim1: TWIM1,
+    /// SPI master 2
+    pub spim2: SPIM2,
+    /// The only SPI master running at 16 and 32 MHz
+    pub spim3: SPIM3,
+    /// UART 0 with EasyDMA
+    pub uarte0: UARTE0,
+    /// UART 1 with EasyDMA
+    pub uarte1: UARTE1,
+    /// Real-time counter 0, with three compare channels
+    pub rtc0: RTC0,
+    /// Real-time counter 1, with four compare channels
+    pub rtc1: RTC1,
+    /// Real-time counter 2, with four compare channels
+    pub rtc2: RTC2,
+}
+impl Peripherals {
+    /// Take the peripherals, returning `None` if they have already been taken
+    pub fn take() -> Option<Self> {
+        critical_section::with(|cs| {
+            let taken = TAKEN.borrow(cs);
+            if taken.replace(true) {
+                None
+            } else {
+                Some(unsafe { Self::steal() })
+            }
+        })
+    }
+    /// Get the peripherals whether or not they have already been taken
+    ///
+    /// # Safety
+    ///
+    /// Handing out a peripheral twice lets two owners configure it behind
+    /// each other's backs, which the typestates can't account for.
+    pub unsafe fn steal() -> Self {
+        Peripherals {
+            p0: P0,
+            p1: P1,
+            clock: CLOCK,
+            gpiote: GPIOTE,
+            timer0: TIMER0,
+            timer1: TIMER1,
+            timer2: TIMER2,
+            timer3: TIMER3,
+            timer4: TIMER4,
+            twim0: TWIM0,
+            twim1: TWIM1,
+            spim2: SPIM2,
+            spim3: SPIM3,
+            uarte0: UARTE0,
+            uarte1: UARTE1,
+            rtc0: RTC0,
+            rtc1: RTC1,
+            rtc2: RTC2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_hands_out_peripherals_once() {
+        let peripherals = Peripherals::take();
+        assert!(peripherals.is_some());
+        assert!(Peripherals::take().is_none());
+        let parts = peripherals.unwrap().p1.split();
+        let _ = parts.p1_09.into_floating_input();
+    }
+}
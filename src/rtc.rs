@@ -0,0 +1,391 @@
+//! RTC0 to RTC2, the real time counters.
+//!
+//! The RTCs count the 32.768 kHz low frequency clock through a 12-bit
+//! prescaler into a 24-bit counter, and keep counting while the CPU sleeps.
+//! The low frequency clock has to be started first, see
+//! [`CLOCK::start_lfclk`](crate::clock::CLOCK::start_lfclk). RTC0 implements
+//! three compare channels, RTC1 and RTC2 four.
+//!
+//! [`Rtc`] drives the counter, its events and channels. [`Monotonic`] runs an
+//! RTC unprescaled as the source of [`crate::time::now`], counting
+//! the overflows of the counter to extend it to 64 bits. Overflows are
+//! counted whenever the time is read, and by [`Monotonic::on_interrupt`] so
+//! that the time doesn't jump back if it isn't read for 512 seconds:
+//!
+//! ```ignore
+//! let mut board = Board::take().unwrap();
+//! board.clock.start_lfclk(LfclkSource::Rc, None)?;
+//! let _clock = Monotonic::new(board.rtc1);
+//! unsafe { NVIC::unmask(Interrupt::RTC1) };
+//! let start = time::now().unwrap();
+//!
+//! #[interrupt]
+//! fn RTC1() {
+//!     Monotonic::<RTC1>::on_interrupt();
+//! }
+//! ```
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::time::{self, Instant};
+use crate::{
+    Addressable, Error, ErrorKind, Interrupt, Read, ReadArray, Register, RegisterArray, Write,
+    WriteArray,
+};
+
+micro_macro::svd! { "svd/nrf52833.svd", RTC0, RTC1, RTC2 }
+
+/// Number of `CC` registers in the register layout
+pub const CC_COUNT: usize = 4;
+/// Largest prescaler, counting at 32.768 kHz / 4096
+pub const MAX_PRESCALER: u16 = 0xFFF;
+/// Width of the counter
+pub const COUNTER_BITS: u32 = 24;
+/// Largest value of the counter and of the compare registers
+pub const COUNTER_MAX: u32 = (1 << COUNTER_BITS) - 1;
+/// Frequency of the low frequency clock the prescaler divides
+const LFCLK_HZ: u32 = time::TICK_HZ as u32;
+
+/// Overflows of the counter of each RTC running as a [`Monotonic`] clock
+static PERIODS: Mutex<[Cell<u32>; 3]> = Mutex::new([const { Cell::new(0) }; 3]);
+
+/// Registers and interrupt of one of the RTCs
+#[allow(non_camel_case_types)]
+pub trait Instance: Addressable {
+    /// Position among the RTCs
+    const INDEX: usize;
+    /// Compare channels the RTC implements
+    const CHANNELS: usize;
+    const INTERRUPT: Interrupt;
+    type TASKS_START: TASKS_START + Register<Instance = Self>;
+    type TASKS_STOP: TASKS_STOP + Register<Instance = Self>;
+    type TASKS_CLEAR: TASKS_CLEAR + Register<Instance = Self>;
+    type TASKS_TRIGOVRFLW: TASKS_TRIGOVRFLW + Register<Instance = Self>;
+    type EVENTS_TICK: EVENTS_TICK + Register<Instance = Self>;
+    type EVENTS_OVRFLW: EVENTS_OVRFLW + Register<Instance = Self>;
+    type EVENTS_COMPARE: EVENTS_COMPARE + RegisterArray<CC_COUNT, Instance = Self>;
+    type INTENSET: INTENSET + Register<Instance = Self>;
+    type INTENCLR: INTENCLR + Register<Instance = Self>;
+    type EVTENSET: EVTENSET + Register<Instance = Self>;
+    type EVTENCLR: EVTENCLR + Register<Instance = Self>;
+    type COUNTER: COUNTER + Register<Instance = Self>;
+    type PRESCALER: PRESCALER + Register<Instance = Self>;
+    type CC: CC + RegisterArray<CC_COUNT, Instance = Self>;
+}
+macro_rules! def_instance {
+    ($($rtc:ident, $n:literal, $channels:literal);+ $(;)?) => {
+        $(paste::paste! {
+            impl Instance for $rtc {
+                const INDEX: usize = $n;
+                const CHANNELS: usize = $channels;
+                const INTERRUPT: Interrupt = Interrupt::$rtc;
+                type TASKS_START = [<TASKS_START $n>];
+                type TASKS_STOP = [<TASKS_STOP $n>];
+                type TASKS_CLEAR = [<TASKS_CLEAR $n>];
+                type TASKS_TRIGOVRFLW = [<TASKS_TRIGOVRFLW $n>];
+                type EVENTS_TICK = [<EVENTS_TICK $n>];
+                type EVENTS_OVRFLW = [<EVENTS_OVRFLW $n>];
+                type EVENTS_COMPARE = [<EVENTS_COMPARE $n>];
+                type INTENSET = [<INTENSET $n>];
+                type INTENCLR = [<INTENCLR $n>];
+                type EVTENSET = [<EVTENSET $n>];
+                type EVTENCLR = [<EVTENCLR $n>];
+                type COUNTER = [<COUNTER $n>];
+                type PRESCALER = [<PRESCALER $n>];
+                type CC = [<CC $n>];
+            }
+        })+
+    };
+}
+def_instance!(
+    RTC0, 0, 3;
+    RTC1, 1, 4;
+    RTC2, 2, 4;
+);
+
+/// Events an RTC can interrupt on or route to other peripherals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Every increment of the counter
+    Tick,
+    /// The counter wrapping from [`COUNTER_MAX`] to 0
+    Overflow,
+    /// The counter reaching the compare value of a channel
+    Compare(usize),
+}
+impl Event {
+    /// Bit of the event in `INTENSET`/`INTENCLR` and `EVTENSET`/`EVTENCLR`
+    const fn bit(self) -> u32 {
+        match self {
+            Event::Tick => 1 << 0,
+            Event::Overflow => 1 << 1,
+            Event::Compare(channel) => 1 << (16 + channel),
+        }
+    }
+}
+/// Bits of all events
+const ALL_EVENTS: u32 = 0b11 | 0xF << 16;
+
+/// A real time counter
+///
+/// Methods taking a compare channel fail with [`ErrorKind::BadIndex`] for
+/// channels the RTC doesn't implement.
+pub struct Rtc<T: Instance> {
+    rtc: T,
+}
+impl<T: Instance> Rtc<T> {
+    /// Take `rtc` and set it up stopped and cleared, counting at 32.768 kHz
+    /// / (`prescaler` + 1) once started, without interrupts or routed events
+    ///
+    /// Fails with [`ErrorKind::InvalidConfig`] above [`MAX_PRESCALER`].
+    pub fn new(rtc: T, prescaler: u16) -> Result<Self, Error> {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+        let mut rtc = Rtc { rtc };
+        rtc.set_prescaler(prescaler)?;
+        T::INTENCLR::write(|w| w.bits(ALL_EVENTS));
+        T::EVTENCLR::write(|w| w.bits(ALL_EVENTS));
+        T::TASKS_CLEAR::write(|w| w.tasks_clear().trigger());
+        Ok(rtc)
+    }
+    /// Stop the RTC, drop its interrupts and routed events and give it back
+    pub fn free(self) -> T {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+        T::INTENCLR::write(|w| w.bits(ALL_EVENTS));
+        T::EVTENCLR::write(|w| w.bits(ALL_EVENTS));
+        self.rtc
+    }
+
+    #[inline]
+    pub fn start(&mut self) {
+        T::TASKS_START::write(|w| w.tasks_start().trigger());
+    }
+    #[inline]
+    pub fn stop(&mut self) {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+    }
+    /// Set the counter back to 0, running or not
+    #[inline]
+    pub fn clear(&mut self) {
+        T::TASKS_CLEAR::write(|w| w.tasks_clear().trigger());
+    }
+    /// Set the counter to 16 ticks before it overflows, to test overflow
+    /// handling
+    #[inline]
+    pub fn trigger_overflow(&mut self) {
+        T::TASKS_TRIGOVRFLW::write(|w| w.tasks_trigovrflw().trigger());
+    }
+
+    /// Count at 32.768 kHz / (`prescaler` + 1); the RTC ignores the change
+    /// while running
+    ///
+    /// Fails with [`ErrorKind::InvalidConfig`] above [`MAX_PRESCALER`].
+    pub fn set_prescaler(&mut self, prescaler: u16) -> Result<(), Error> {
+        if prescaler > MAX_PRESCALER {
+            return Err(ErrorKind::InvalidConfig.into());
+        }
+        T::PRESCALER::write(|w| w.prescaler().bits(prescaler as u32));
+        Ok(())
+    }
+    /// Counts per second at the current prescaler
+    pub fn frequency(&self) -> u32 {
+        LFCLK_HZ / (T::PRESCALER::read().prescaler().bits() + 1)
+    }
+    /// Current value of the counter
+    #[inline]
+    pub fn counter(&self) -> u32 {
+        T::COUNTER::read().counter().bits()
+    }
+
+    /// Raise the compare event of `channel` when the counter reaches `value`
+    ///
+    /// Fails with [`ErrorKind::InvalidConfig`] above [`COUNTER_MAX`].
+    pub fn set_compare(&mut self, channel: usize, value: u32) -> Result<(), Error> {
+        let channel = Self::check(channel)?;
+        if value > COUNTER_MAX {
+            return Err(ErrorKind::InvalidConfig.into());
+        }
+        T::CC::write(channel, |w| w.compare().bits(value));
+        Ok(())
+    }
+    /// Interrupt on `event`
+    pub fn listen(&mut self, event: Event) -> Result<(), Error> {
+        let bit = Self::event_bit(event)?;
+        T::INTENSET::write(|w| w.bits(bit));
+        Ok(())
+    }
+    /// Stop interrupting on `event`
+    pub fn unlisten(&mut self, event: Event) -> Result<(), Error> {
+        let bit = Self::event_bit(event)?;
+        T::INTENCLR::write(|w| w.bits(bit));
+        Ok(())
+    }
+    /// Route `event` to PPI, which raises it even without interrupting on it
+    pub fn enable_event(&mut self, event: Event) -> Result<(), Error> {
+        let bit = Self::event_bit(event)?;
+        T::EVTENSET::write(|w| w.bits(bit));
+        Ok(())
+    }
+    /// Stop routing `event` to PPI
+    pub fn disable_event(&mut self, event: Event) -> Result<(), Error> {
+        let bit = Self::event_bit(event)?;
+        T::EVTENCLR::write(|w| w.bits(bit));
+        Ok(())
+    }
+    /// Whether `event` has been raised since it was last reset
+    ///
+    /// Events are only raised while interrupting on them or routing them.
+    pub fn is_event(&self, event: Event) -> Result<bool, Error> {
+        Ok(match event {
+            Event::Tick => T::EVENTS_TICK::read().events_tick().is_generated(),
+            Event::Overflow => T::EVENTS_OVRFLW::read().events_ovrflw().is_generated(),
+            Event::Compare(channel) => T::EVENTS_COMPARE::read(Self::check(channel)?)
+                .events_compare()
+                .is_generated(),
+        })
+    }
+    /// Reset `event`
+    pub fn reset_event(&mut self, event: Event) -> Result<(), Error> {
+        match event {
+            Event::Tick => T::EVENTS_TICK::write(|w| w.events_tick().notgenerated()),
+            Event::Overflow => T::EVENTS_OVRFLW::write(|w| w.events_ovrflw().notgenerated()),
+            Event::Compare(channel) => T::EVENTS_COMPARE::write(Self::check(channel)?, |w| {
+                w.events_compare().notgenerated()
+            }),
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn check(channel: usize) -> Result<usize, Error> {
+        if channel < T::CHANNELS {
+            Ok(channel)
+        } else {
+            Err(ErrorKind::BadIndex.into())
+        }
+    }
+    #[inline]
+    fn event_bit(event: Event) -> Result<u32, Error> {
+        if let Event::Compare(channel) = event {
+            Self::check(channel)?;
+        }
+        Ok(event.bit())
+    }
+}
+
+/// An RTC counting every tick of the low frequency clock as the monotonic
+/// clock of the crate
+///
+/// Creating one makes it the source of [`time::now`], replacing any other.
+pub struct Monotonic<T: Instance> {
+    rtc: Rtc<T>,
+}
+impl<T: Instance> Monotonic<T> {
+    /// Take `rtc` and start counting from [`Instant::ZERO`], interrupting on
+    /// the overflow of the counter
+    pub fn new(rtc: T) -> Self {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+        let mut rtc = Rtc { rtc };
+        T::PRESCALER::write(|w| w.prescaler().bits(0));
+        T::INTENCLR::write(|w| w.bits(ALL_EVENTS));
+        T::EVTENCLR::write(|w| w.bits(ALL_EVENTS));
+        T::INTENSET::write(|w| w.bits(Event::Overflow.bit()));
+        T::EVENTS_OVRFLW::write(|w| w.events_ovrflw().notgenerated());
+        critical_section::with(|cs| PERIODS.borrow(cs)[T::INDEX].set(0));
+        rtc.clear();
+        rtc.start();
+        time::set_source(Some(Self::read));
+        Monotonic { rtc }
+    }
+    /// Stop the clock and give the RTC back, leaving [`time::now`] without a
+    /// source unless another clock has replaced this one
+    pub fn free(self) -> T {
+        time::clear_source(Self::read);
+        self.rtc.free()
+    }
+    /// Current time
+    #[inline]
+    pub fn now(&self) -> Instant {
+        Self::read()
+    }
+    /// Count an overflow of the counter, call this from the interrupt of the
+    /// RTC
+    pub fn on_interrupt() {
+        critical_section::with(|cs| Self::take_overflow(&PERIODS.borrow(cs)[T::INDEX]));
+    }
+
+    fn read() -> Instant {
+        critical_section::with(|cs| {
+            let periods = &PERIODS.borrow(cs)[T::INDEX];
+            Self::take_overflow(periods);
+            let mut counter = T::COUNTER::read().counter().bits();
+            // The counter may have wrapped between the two reads
+            if Self::take_overflow(periods) {
+                counter = T::COUNTER::read().counter().bits();
+            }
+            Instant::from_ticks((periods.get() as u64) << COUNTER_BITS | counter as u64)
+        })
+    }
+    fn take_overflow(periods: &Cell<u32>) -> bool {
+        let overflowed = T::EVENTS_OVRFLW::read().events_ovrflw().is_generated();
+        if overflowed {
+            T::EVENTS_OVRFLW::write(|w| w.events_ovrflw().notgenerated());
+            periods.set(periods.get() + 1);
+        }
+        overflowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::sim;
+
+    #[test]
+    fn rtc_configures_prescaler_channels_and_events() {
+        sim::reset();
+        let error = Rtc::new(RTC0, 0x1000).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidConfig);
+        let mut rtc = Rtc::new(RTC0, 327).unwrap();
+        assert_eq!(rtc.frequency(), 99);
+        assert_eq!(sim::peek(INTENCLR0::ADDR), ALL_EVENTS);
+
+        rtc.set_compare(2, COUNTER_MAX).unwrap();
+        assert_eq!(sim::peek(<RTC0 as Instance>::CC::ADDRS[2]), COUNTER_MAX);
+        let error = rtc.set_compare(3, 0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BadIndex);
+        let error = rtc.set_compare(0, 1 << 24).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidConfig);
+
+        rtc.listen(Event::Compare(1)).unwrap();
+        assert_eq!(sim::peek(INTENSET0::ADDR), 1 << 17);
+        rtc.enable_event(Event::Tick).unwrap();
+        assert_eq!(sim::peek(EVTENSET0::ADDR), 1);
+        assert!(rtc.listen(Event::Compare(3)).is_err());
+
+        sim::poke(EVENTS_COMPARE0::ADDRS[1], 1);
+        assert!(rtc.is_event(Event::Compare(1)).unwrap());
+        rtc.reset_event(Event::Compare(1)).unwrap();
+        assert!(!rtc.is_event(Event::Compare(1)).unwrap());
+        assert_eq!(<RTC2 as Instance>::COUNTER::ADDR, 0x4002_4504);
+    }
+
+    #[test]
+    fn monotonic_time_counts_overflows() {
+        sim::reset();
+        let clock = Monotonic::new(RTC1);
+        assert_eq!(sim::peek(PRESCALER1::ADDR), 0);
+        assert_eq!(sim::peek(INTENSET1::ADDR), Event::Overflow.bit());
+        sim::poke(COUNTER1::ADDR, 100);
+        assert_eq!(clock.now(), Instant::from_ticks(100));
+
+        sim::poke(EVENTS_OVRFLW1::ADDR, 1);
+        sim::poke(COUNTER1::ADDR, 5);
+        assert_eq!(clock.now().ticks(), 1 << 24 | 5);
+        assert_eq!(sim::peek(EVENTS_OVRFLW1::ADDR), 0);
+        sim::poke(EVENTS_OVRFLW1::ADDR, 1);
+        Monotonic::<RTC1>::on_interrupt();
+        assert_eq!(clock.now().ticks(), 2 << 24 | 5);
+        assert!(time::now().is_some());
+    }
+}
@@ -0,0 +1,530 @@
+//! SPIM0 to SPIM3, the SPI masters with EasyDMA.
+//!
+//! A transfer clocks out `TXD.MAXCNT` bytes from RAM while clocking in
+//! `RXD.MAXCNT` bytes to RAM, sending the over-read character
+//! ([`Config::orc`]) once the bytes to send run out and dropping what arrives
+//! once the receive buffer is full. Transfers longer than EasyDMA can move
+//! at once, or sending data that isn't in RAM, are split into several
+//! transfers while the chip select stays asserted.
+//!
+//! SPIM0 and SPIM1 share their registers with TWIM0 and TWIM1 and are
+//! obtained from them with [`TWIM0::into_spim`] and [`TWIM1::into_spim`].
+//! Only SPIM3 runs at 16 and 32 MHz.
+//!
+//! ```ignore
+//! let board = Board::take().unwrap();
+//! let sck = board.edge.p13.into_push_pull_output(Level::Low);
+//! let mosi = board.edge.p15.into_push_pull_output(Level::Low);
+//! let miso = board.edge.p14.into_floating_input();
+//! let cs = board.edge.p16.into_push_pull_output(Level::High);
+//! let mut spi = Spim::new(board.spim3, sck, mosi, miso, Config::default())?
+//!     .with_chip_select(cs);
+//! let mut id = [0x9F, 0, 0, 0];
+//! spi.transfer_in_place(&mut id)?;
+//! ```
+use crate::gpio::{Handle, Input, Output, PushPull};
+use crate::mem::in_ram;
+use crate::time::{Deadline, Duration};
+use crate::twim::{TWIM0, TWIM1};
+use crate::{Addressable, Error, ErrorKind, Interrupt, Pin, Read, Register, Write};
+
+micro_macro::svd! { "svd/nrf52833.svd", SPIM0, SPIM1, SPIM2, SPIM3 }
+
+/// Most bytes a single EasyDMA transfer can move, the width of `MAXCNT`
+pub const MAX_TRANSFER: usize = 0xFFFF;
+/// Size of the stack buffer data outside RAM is copied through
+const COPY_CHUNK: usize = 32;
+
+/// Registers and interrupt of one of the SPIMs
+#[allow(non_camel_case_types)]
+pub trait Instance: Addressable {
+    const INTERRUPT: Interrupt;
+    /// Whether the instance runs at 16 and 32 MHz
+    const HIGH_SPEED: bool;
+    type TASKS_START: TASKS_START + Register<Instance = Self>;
+    type TASKS_STOP: TASKS_STOP + Register<Instance = Self>;
+    type EVENTS_END: EVENTS_END + Register<Instance = Self>;
+    type EVENTS_STOPPED: EVENTS_STOPPED + Register<Instance = Self>;
+    type ENABLE: ENABLE + Register<Instance = Self>;
+    type PSEL_SCK: PSEL_SCK + Register<Instance = Self>;
+    type PSEL_MOSI: PSEL_MOSI + Register<Instance = Self>;
+    type PSEL_MISO: PSEL_MISO + Register<Instance = Self>;
+    type FREQUENCY: FREQUENCY + Register<Instance = Self>;
+    type RXD_PTR: RXD_PTR + Register<Instance = Self>;
+    type RXD_MAXCNT: RXD_MAXCNT + Register<Instance = Self>;
+    type TXD_PTR: TXD_PTR + Register<Instance = Self>;
+    type TXD_MAXCNT: TXD_MAXCNT + Register<Instance = Self>;
+    type CONFIG: CONFIG + Register<Instance = Self>;
+    type ORC: ORC + Register<Instance = Self>;
+}
+macro_rules! def_instance {
+    ($($spim:ident, $n:literal, $interrupt:ident, $high_speed:literal);+ $(;)?) => {
+        $(paste::paste! {
+            impl Instance for $spim {
+                const INTERRUPT: Interrupt = Interrupt::$interrupt;
+                const HIGH_SPEED: bool = $high_speed;
+                type TASKS_START = [<TASKS_START $n>];
+                type TASKS_STOP = [<TASKS_STOP $n>];
+                type EVENTS_END = [<EVENTS_END $n>];
+                type EVENTS_STOPPED = [<EVENTS_STOPPED $n>];
+                type ENABLE = [<ENABLE $n>];
+                type PSEL_SCK = [<PSEL_SCK $n>];
+                type PSEL_MOSI = [<PSEL_MOSI $n>];
+                type PSEL_MISO = [<PSEL_MISO $n>];
+                type FREQUENCY = [<FREQUENCY $n>];
+                type RXD_PTR = [<RXD_PTR $n>];
+                type RXD_MAXCNT = [<RXD_MAXCNT $n>];
+                type TXD_PTR = [<TXD_PTR $n>];
+                type TXD_MAXCNT = [<TXD_MAXCNT $n>];
+                type CONFIG = [<CONFIG $n>];
+                type ORC = [<ORC $n>];
+            }
+        })+
+    };
+}
+def_instance!(
+    SPIM0, 0, SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0, false;
+    SPIM1, 1, SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1, false;
+    SPIM2, 2, SPIM2_SPIS2_SPI2, false;
+    SPIM3, 3, SPIM3, true;
+);
+
+macro_rules! def_shared {
+    ($($twim:ident, $spim:ident);+ $(;)?) => {
+        $(
+            impl $twim {
+                #[doc = concat!("Use the registers as ", stringify!($spim), " instead")]
+                pub fn into_spim(self) -> $spim {
+                    $spim
+                }
+            }
+            impl $spim {
+                #[doc = concat!("Use the registers as ", stringify!($twim), " instead")]
+                pub fn into_twim(self) -> $twim {
+                    $twim
+                }
+            }
+        )+
+    };
+}
+def_shared!(
+    TWIM0, SPIM0;
+    TWIM1, SPIM1;
+);
+
+/// Clock polarity and phase
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Clock idles low, data is sampled on the rising edge
+    Mode0,
+    /// Clock idles low, data is sampled on the falling edge
+    Mode1,
+    /// Clock idles high, data is sampled on the falling edge
+    Mode2,
+    /// Clock idles high, data is sampled on the rising edge
+    Mode3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+/// Clock frequency of the bus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    K125,
+    K250,
+    K500,
+    M1,
+    M2,
+    M4,
+    M8,
+    /// SPIM3 only
+    M16,
+    /// SPIM3 only
+    M32,
+}
+impl From<Frequency> for frequency::FREQUENCY_A {
+    fn from(frequency: Frequency) -> Self {
+        match frequency {
+            Frequency::K125 => frequency::FREQUENCY_A::K125,
+            Frequency::K250 => frequency::FREQUENCY_A::K250,
+            Frequency::K500 => frequency::FREQUENCY_A::K500,
+            Frequency::M1 => frequency::FREQUENCY_A::M1,
+            Frequency::M2 => frequency::FREQUENCY_A::M2,
+            Frequency::M4 => frequency::FREQUENCY_A::M4,
+            Frequency::M8 => frequency::FREQUENCY_A::M8,
+            Frequency::M16 => frequency::FREQUENCY_A::M16,
+            Frequency::M32 => frequency::FREQUENCY_A::M32,
+        }
+    }
+}
+
+/// Bus settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub mode: Mode,
+    pub bit_order: BitOrder,
+    pub frequency: Frequency,
+    /// Byte sent once the bytes to write have run out
+    pub orc: u8,
+}
+/// Mode 0, MSB first at 1 MHz, sending `0xFF` when there is nothing to write
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            mode: Mode::Mode0,
+            bit_order: BitOrder::MsbFirst,
+            frequency: Frequency::M1,
+            orc: 0xFF,
+        }
+    }
+}
+
+/// A chip select line, asserted for the length of each transfer
+pub trait ChipSelect {
+    fn select(&mut self);
+    fn deselect(&mut self);
+}
+/// No chip select, for a single device that has its own tied low or one
+/// selected by hand
+impl ChipSelect for () {
+    fn select(&mut self) {}
+    fn deselect(&mut self) {}
+}
+/// An active low chip select
+impl<P: Pin> ChipSelect for Handle<P, Output<PushPull>> {
+    fn select(&mut self) {
+        self.set_low()
+    }
+    fn deselect(&mut self) {
+        self.set_high()
+    }
+}
+
+/// An SPIM driving a clock and two data lines, and the chip select `CS`
+pub struct Spim<T: Instance, SCK: Pin, MOSI: Pin, MISO: Pin, PULL, CS: ChipSelect = ()> {
+    spim: T,
+    pins: Pins<SCK, MOSI, MISO, PULL>,
+    cs: CS,
+    timeout: Option<Duration>,
+}
+/// The clock and data lines of an SPIM
+pub struct Pins<SCK: Pin, MOSI: Pin, MISO: Pin, PULL> {
+    pub sck: Handle<SCK, Output<PushPull>>,
+    pub mosi: Handle<MOSI, Output<PushPull>>,
+    pub miso: Handle<MISO, Input<PULL>>,
+}
+impl<T: Instance, SCK: Pin, MOSI: Pin, MISO: Pin, PULL> Spim<T, SCK, MOSI, MISO, PULL> {
+    /// Take `spim` and the `sck`, `mosi` and `miso` pins, route the SPIM to
+    /// them and enable it with `config`
+    ///
+    /// The pins keep their configuration; `sck` should start at the level
+    /// the clock idles at in the chosen [`Mode`]. Fails with
+    /// [`ErrorKind::InvalidConfig`], before touching the SPIM and dropping
+    /// it and the pins, if the instance can't run at the frequency of
+    /// `config`.
+    pub fn new(
+        spim: T,
+        sck: Handle<SCK, Output<PushPull>>,
+        mosi: Handle<MOSI, Output<PushPull>>,
+        miso: Handle<MISO, Input<PULL>>,
+        config: Config,
+    ) -> Result<Self, Error> {
+        let mut spi = Spim {
+            spim,
+            pins: Pins { sck, mosi, miso },
+            cs: (),
+            timeout: None,
+        };
+        spi.set_config(config)?;
+        T::ENABLE::write(|w| w.enable().disabled());
+        T::PSEL_SCK::write(|w| {
+            w.pin()
+                .bits(SCK::PIN_ID as u32)
+                .port()
+                .bits(SCK::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::PSEL_MOSI::write(|w| {
+            w.pin()
+                .bits(MOSI::PIN_ID as u32)
+                .port()
+                .bits(MOSI::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::PSEL_MISO::write(|w| {
+            w.pin()
+                .bits(MISO::PIN_ID as u32)
+                .port()
+                .bits(MISO::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::ENABLE::write(|w| w.enable().enabled());
+        Ok(spi)
+    }
+    /// Drive `cs` low for the length of every transfer, starting out
+    /// deselected
+    pub fn with_chip_select<P: Pin>(
+        self,
+        mut cs: Handle<P, Output<PushPull>>,
+    ) -> Spim<T, SCK, MOSI, MISO, PULL, Handle<P, Output<PushPull>>> {
+        cs.deselect();
+        Spim {
+            spim: self.spim,
+            pins: self.pins,
+            cs,
+            timeout: self.timeout,
+        }
+    }
+}
+impl<T: Instance, SCK: Pin, MOSI: Pin, MISO: Pin, PULL, CS: ChipSelect>
+    Spim<T, SCK, MOSI, MISO, PULL, CS>
+{
+    /// Disable the SPIM and give it back along with its pins and the chip
+    /// select
+    pub fn free(self) -> (T, Pins<SCK, MOSI, MISO, PULL>, CS) {
+        T::ENABLE::write(|w| w.enable().disabled());
+        T::PSEL_SCK::write(|w| w.connect().disconnected());
+        T::PSEL_MOSI::write(|w| w.connect().disconnected());
+        T::PSEL_MISO::write(|w| w.connect().disconnected());
+        (self.spim, self.pins, self.cs)
+    }
+    /// Change the mode, bit order, frequency and over-read character
+    ///
+    /// Fails with [`ErrorKind::InvalidConfig`], changing nothing, if the
+    /// instance can't run at the frequency of `config`.
+    pub fn set_config(&mut self, config: Config) -> Result<(), Error> {
+        if matches!(config.frequency, Frequency::M16 | Frequency::M32) && !T::HIGH_SPEED {
+            return Err(ErrorKind::InvalidConfig.into());
+        }
+        T::CONFIG::write(|w| {
+            let w = match config.bit_order {
+                BitOrder::MsbFirst => w.order().msbfirst(),
+                BitOrder::LsbFirst => w.order().lsbfirst(),
+            };
+            match config.mode {
+                Mode::Mode0 => w.cpol().activehigh().cpha().leading(),
+                Mode::Mode1 => w.cpol().activehigh().cpha().trailing(),
+                Mode::Mode2 => w.cpol().activelow().cpha().leading(),
+                Mode::Mode3 => w.cpol().activelow().cpha().trailing(),
+            }
+        });
+        T::FREQUENCY::write(|w| w.frequency().variant(config.frequency.into()));
+        T::ORC::write(|w| w.orc().bits(config.orc as u32));
+        Ok(())
+    }
+    /// Give up on a transfer that hasn't ended after `timeout`, as measured
+    /// by [`time::now`](crate::time::now), or wait forever with `None`, the
+    /// default
+    ///
+    /// A transfer that times out is stopped and fails with
+    /// [`ErrorKind::Timeout`]. With a timeout set, every transfer fails with
+    /// [`ErrorKind::InvalidConfig`] while no monotonic clock is running.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Send `write` while receiving into `read`, sending the over-read
+    /// character past the end of `write` and dropping bytes past the end of
+    /// `read`
+    ///
+    /// The chip select is released again even if the transfer fails, which
+    /// it only does with [`ErrorKind::Timeout`] or [`ErrorKind::InvalidConfig`]
+    /// as set by [`set_timeout`](Self::set_timeout).
+    pub fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Error> {
+        self.cs.select();
+        let mut result = Ok(());
+        let len = read.len().max(write.len());
+        // An empty slice has nothing to copy, wherever it points
+        let copy = !write.is_empty() && !in_ram(write);
+        let chunk = if copy { COPY_CHUNK } else { MAX_TRANSFER };
+        let mut buffer = [0; COPY_CHUNK];
+        for start in (0..len).step_by(chunk) {
+            let end = start + chunk;
+            let rx_len = read.len();
+            let rx = &mut read[start.min(rx_len)..end.min(rx_len)];
+            let mut tx = &write[start.min(write.len())..end.min(write.len())];
+            if copy {
+                buffer[..tx.len()].copy_from_slice(tx);
+                tx = &buffer[..tx.len()];
+            }
+            result = Self::run(
+                rx.as_mut_ptr(),
+                rx.len(),
+                tx.as_ptr(),
+                tx.len(),
+                self.timeout,
+            );
+            if result.is_err() {
+                break;
+            }
+        }
+        self.cs.deselect();
+        result
+    }
+    /// Send the bytes of `buffer`, replacing each with the byte received at
+    /// the same time, failing like [`transfer`](Self::transfer)
+    pub fn transfer_in_place(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        self.cs.select();
+        let mut result = Ok(());
+        // EasyDMA reads each byte before the one received in its place is
+        // written
+        for chunk in buffer.chunks_mut(MAX_TRANSFER) {
+            let (ptr, len) = (chunk.as_mut_ptr(), chunk.len());
+            result = Self::run(ptr, len, ptr, len, self.timeout);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.cs.deselect();
+        result
+    }
+    /// Send `bytes`, ignoring what comes back
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.transfer(&mut [], bytes)
+    }
+    /// Fill `buffer`, sending the over-read character
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        self.transfer(buffer, &[])
+    }
+
+    /// Run one EasyDMA transfer and wait for it to end, stopping it if it
+    /// hasn't after `timeout`
+    fn run(
+        rx: *mut u8,
+        rx_len: usize,
+        tx: *const u8,
+        tx_len: usize,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        let deadline = Deadline::after(timeout)?;
+        T::RXD_PTR::write(|w| w.ptr().bits(rx as u32));
+        T::RXD_MAXCNT::write(|w| w.maxcnt().bits(rx_len as u32));
+        T::TXD_PTR::write(|w| w.ptr().bits(tx as u32));
+        T::TXD_MAXCNT::write(|w| w.maxcnt().bits(tx_len as u32));
+        T::TASKS_START::write(|w| w.tasks_start().trigger());
+        let ended = deadline.wait(|| T::EVENTS_END::read().events_end().is_generated());
+        if ended.is_err() {
+            T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+            let stopped = Deadline::after(timeout)?
+                .wait(|| T::EVENTS_STOPPED::read().events_stopped().is_generated());
+            T::EVENTS_STOPPED::write(|w| w.events_stopped().notgenerated());
+            stopped?;
+        }
+        T::EVENTS_END::write(|w| w.events_end().notgenerated());
+        ended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::gpio::Floating;
+    use crate::mem::sim;
+    use crate::rtc::{Monotonic, RTC2};
+    use crate::{Level, Peripherals, OUTCLR1, OUTSET1, P001, P013, P017};
+
+    type Spi<T, CS = ()> = Spim<T, P017, P013, P001, Floating, CS>;
+
+    fn spi<T: Instance>(spim: T, config: Config) -> Result<Spi<T>, Error> {
+        sim::reset();
+        let pins = unsafe { Peripherals::steal() }.p0.split();
+        let sck = pins.p0_17.into_push_pull_output(Level::Low);
+        let mosi = pins.p0_13.into_push_pull_output(Level::Low);
+        let miso = pins.p0_01.into_floating_input();
+        Spim::new(spim, sck, mosi, miso, config)
+    }
+
+    #[test]
+    fn pins_and_bus_settings_are_configured() {
+        let config = Config {
+            mode: Mode::Mode3,
+            bit_order: BitOrder::LsbFirst,
+            frequency: Frequency::M32,
+            orc: 0,
+        };
+        let _spi = spi(SPIM3, config).unwrap();
+        assert_eq!(sim::peek(PSEL_SCK3::ADDR), 17);
+        assert_eq!(sim::peek(PSEL_MOSI3::ADDR), 13);
+        assert_eq!(sim::peek(PSEL_MISO3::ADDR), 1);
+        assert_eq!(sim::peek(CONFIG3::ADDR), 0b111);
+        assert_eq!(sim::peek(FREQUENCY3::ADDR), 0x1400_0000);
+        assert_eq!(sim::peek(ENABLE3::ADDR), 7);
+        assert_eq!(<SPIM2 as Instance>::CONFIG::ADDR, 0x4002_3554);
+    }
+
+    #[test]
+    fn only_spim3_runs_past_8_mhz() {
+        let config = Config {
+            frequency: Frequency::M16,
+            ..Config::default()
+        };
+        let board = Board::new(unsafe { Peripherals::steal() });
+        let error = spi(board.twim0.into_spim(), config).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidConfig);
+        assert!(spi(SPIM2, config).is_err());
+        assert!(spi(SPIM3, config).is_ok());
+    }
+
+    #[test]
+    fn chip_select_frames_each_transfer() {
+        let spi = spi(SPIM3, Config::default()).unwrap();
+        let pins = unsafe { Peripherals::steal() }.p1.split();
+        let cs = pins.p1_02.into_push_pull_output(Level::Low);
+        let mut spi = spi.with_chip_select(cs);
+        let mut read = [0; 2];
+        sim::poke(EVENTS_END3::ADDR, 1);
+        spi.transfer(&mut read, &[1, 2, 3]).unwrap();
+        assert_eq!(sim::peek(RXD_MAXCNT3::ADDR), 2);
+        assert_eq!(sim::peek(TXD_MAXCNT3::ADDR), 3);
+        assert_eq!(sim::peek(RXD_PTR3::ADDR), read.as_ptr() as u32);
+        let writes: Vec<_> = sim::writes()
+            .into_iter()
+            .map(|(addr, _)| addr)
+            .filter(|addr| [OUTCLR1::ADDR, OUTSET1::ADDR, TASKS_START3::ADDR].contains(addr))
+            .collect();
+        // Deselected by `with_chip_select`, then around the transfer
+        assert_eq!(
+            writes[writes.len() - 3..],
+            [OUTCLR1::ADDR, TASKS_START3::ADDR, OUTSET1::ADDR]
+        );
+        let (_, pins, cs) = spi.free();
+        assert!(cs.is_set_high());
+        assert!(pins.sck.is_set_low());
+        assert_eq!(sim::peek(PSEL_SCK3::ADDR), 1 << 31);
+    }
+
+    #[test]
+    fn stuck_transfers_time_out_and_deselect() {
+        let spi = spi(SPIM3, Config::default()).unwrap();
+        let pins = unsafe { Peripherals::steal() }.p1.split();
+        let mut spi = spi.with_chip_select(pins.p1_02.into_push_pull_output(Level::High));
+        let _clock = Monotonic::new(RTC2);
+        spi.set_timeout(Some(Duration::ZERO));
+        // Time stands still in the simulation, so only an empty wait expires
+        let error = spi.write(&[1, 2]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert_eq!(sim::peek(TASKS_STOP3::ADDR), 1);
+        let (_, _, cs) = spi.free();
+        assert!(cs.is_set_high());
+    }
+
+    #[test]
+    fn transfer_in_place_sends_and_receives_one_buffer() {
+        let mut spi = spi(SPIM2, Config::default()).unwrap();
+        let mut buffer = [0x9F, 0, 0];
+        sim::poke(EVENTS_END2::ADDR, 1);
+        spi.transfer_in_place(&mut buffer).unwrap();
+        assert_eq!(sim::peek(RXD_PTR2::ADDR), sim::peek(TXD_PTR2::ADDR));
+        assert_eq!(sim::peek(RXD_MAXCNT2::ADDR), 3);
+        assert_eq!(sim::peek(TXD_MAXCNT2::ADDR), 3);
+        assert_eq!(sim::peek(ORC2::ADDR), 0xFF);
+    }
+}
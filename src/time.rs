@@ -0,0 +1,253 @@
+//! Monotonic time.
+//!
+//! [`Instant`]s and [`Duration`]s count ticks of the 32.768 kHz low frequency
+//! clock in 64 bits, which doesn't wrap for millions of years. The time comes
+//! from an RTC running as a [`Monotonic`](crate::rtc::Monotonic) clock, which
+//! keeps counting while the CPU sleeps; creating one makes it the tick source
+//! of [`now`], which drivers use for their timeouts.
+use core::cell::Cell;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+use critical_section::Mutex;
+
+use crate::{Error, ErrorKind};
+
+/// Ticks per second
+pub const TICK_HZ: u64 = 32_768;
+
+type Source = Option<fn() -> Instant>;
+/// Reads the time of the running monotonic clock
+static SOURCE: Mutex<Cell<Source>> = Mutex::new(Cell::new(None));
+
+pub(crate) fn set_source(source: Source) {
+    critical_section::with(|cs| SOURCE.borrow(cs).set(source))
+}
+/// Leave [`now`] without a source if it still reads `source`, so a clock
+/// going away doesn't take a newer one with it
+pub(crate) fn clear_source(source: fn() -> Instant) {
+    critical_section::with(|cs| {
+        let current = SOURCE.borrow(cs);
+        if current
+            .get()
+            .is_some_and(|read| core::ptr::fn_addr_eq(read, source))
+        {
+            current.set(None)
+        }
+    })
+}
+
+/// Current time of the monotonic clock, or `None` if none is running
+pub fn now() -> Option<Instant> {
+    critical_section::with(|cs| SOURCE.borrow(cs).get()).map(|source| source())
+}
+
+/// When a wait bounded by an optional timeout gives up
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Deadline(Option<Instant>);
+impl Deadline {
+    /// `timeout` from now, or never without a timeout
+    ///
+    /// Fails with [`ErrorKind::InvalidConfig`] if there is a timeout but no
+    /// monotonic clock is running to measure it.
+    pub(crate) fn after(timeout: Option<Duration>) -> Result<Self, Error> {
+        match timeout {
+            None => Ok(Deadline(None)),
+            Some(timeout) => Ok(Deadline(Some(
+                now().ok_or(ErrorKind::InvalidConfig)? + timeout,
+            ))),
+        }
+    }
+    /// Whether the deadline has passed, or the clock measuring it has stopped
+    pub(crate) fn expired(&self) -> bool {
+        self.0
+            .is_some_and(|deadline| now().is_none_or(|now| now >= deadline))
+    }
+    /// Poll `done` until it returns true, failing with [`ErrorKind::Timeout`]
+    /// once the deadline has passed
+    pub(crate) fn wait(&self, mut done: impl FnMut() -> bool) -> Result<(), Error> {
+        while !done() {
+            if self.expired() {
+                return Err(ErrorKind::Timeout.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Point in time, in ticks since the monotonic clock started
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(u64);
+impl Instant {
+    /// When the monotonic clock started
+    pub const ZERO: Instant = Instant(0);
+
+    #[inline]
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Instant(ticks)
+    }
+    #[inline]
+    pub const fn ticks(&self) -> u64 {
+        self.0
+    }
+    /// Time from `earlier` to `self`, zero if `earlier` is later
+    #[inline]
+    pub const fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration(self.0.saturating_sub(earlier.0))
+    }
+    /// Time from `earlier` to `self`, `None` if `earlier` is later
+    #[inline]
+    pub const fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        match self.0.checked_sub(earlier.0) {
+            Some(ticks) => Some(Duration(ticks)),
+            None => None,
+        }
+    }
+}
+impl Add<Duration> for Instant {
+    type Output = Instant;
+    #[inline]
+    fn add(self, duration: Duration) -> Instant {
+        Instant(self.0 + duration.0)
+    }
+}
+impl AddAssign<Duration> for Instant {
+    #[inline]
+    fn add_assign(&mut self, duration: Duration) {
+        self.0 += duration.0
+    }
+}
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+    #[inline]
+    fn sub(self, duration: Duration) -> Instant {
+        Instant(self.0 - duration.0)
+    }
+}
+impl SubAssign<Duration> for Instant {
+    #[inline]
+    fn sub_assign(&mut self, duration: Duration) {
+        self.0 -= duration.0
+    }
+}
+/// Same as [`Instant::duration_since`]
+impl Sub for Instant {
+    type Output = Duration;
+    #[inline]
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+/// Span of time in ticks
+///
+/// Conversions from wall-clock units round up to the next tick, so waiting
+/// for a converted duration never ends early; conversions back round down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(u64);
+impl Duration {
+    pub const ZERO: Duration = Duration(0);
+
+    #[inline]
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Duration(ticks)
+    }
+    #[inline]
+    pub const fn from_micros(micros: u64) -> Self {
+        Duration::from_fraction(micros, 1_000_000)
+    }
+    #[inline]
+    pub const fn from_millis(millis: u64) -> Self {
+        Duration::from_fraction(millis, 1_000)
+    }
+    #[inline]
+    pub const fn from_secs(secs: u64) -> Self {
+        Duration(secs * TICK_HZ)
+    }
+    /// `count` units of 1/`per_sec` of a second, in ticks rounded up
+    const fn from_fraction(count: u64, per_sec: u64) -> Self {
+        Duration((count as u128 * TICK_HZ as u128).div_ceil(per_sec as u128) as u64)
+    }
+
+    #[inline]
+    pub const fn ticks(&self) -> u64 {
+        self.0
+    }
+    #[inline]
+    pub const fn as_micros(&self) -> u64 {
+        (self.0 as u128 * 1_000_000 / TICK_HZ as u128) as u64
+    }
+    #[inline]
+    pub const fn as_millis(&self) -> u64 {
+        (self.0 as u128 * 1_000 / TICK_HZ as u128) as u64
+    }
+    #[inline]
+    pub const fn as_secs(&self) -> u64 {
+        self.0 / TICK_HZ
+    }
+    #[inline]
+    pub const fn checked_sub(self, other: Duration) -> Option<Duration> {
+        match self.0.checked_sub(other.0) {
+            Some(ticks) => Some(Duration(ticks)),
+            None => None,
+        }
+    }
+}
+impl Add for Duration {
+    type Output = Duration;
+    #[inline]
+    fn add(self, other: Duration) -> Duration {
+        Duration(self.0 + other.0)
+    }
+}
+impl AddAssign for Duration {
+    #[inline]
+    fn add_assign(&mut self, other: Duration) {
+        self.0 += other.0
+    }
+}
+impl Sub for Duration {
+    type Output = Duration;
+    #[inline]
+    fn sub(self, other: Duration) -> Duration {
+        Duration(self.0 - other.0)
+    }
+}
+impl SubAssign for Duration {
+    #[inline]
+    fn sub_assign(&mut self, other: Duration) {
+        self.0 -= other.0
+    }
+}
+impl From<Duration> for core::time::Duration {
+    fn from(duration: Duration) -> Self {
+        core::time::Duration::from_micros(duration.as_micros())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_round_towards_longer_waits() {
+        assert_eq!(Duration::from_secs(2).ticks(), 65_536);
+        assert_eq!(Duration::from_millis(1).ticks(), 33);
+        assert_eq!(Duration::from_micros(1).ticks(), 1);
+        assert_eq!(Duration::from_millis(1_000), Duration::from_secs(1));
+        assert_eq!(Duration::from_ticks(33).as_millis(), 1);
+        assert_eq!(Duration::from_ticks(1).as_micros(), 30);
+        let micros: core::time::Duration = Duration::from_secs(3).into();
+        assert_eq!(micros.as_secs(), 3);
+    }
+
+    #[test]
+    fn instants_are_ordered_by_ticks() {
+        let start = Instant::from_ticks(1 << 40);
+        let later = start + Duration::from_secs(1);
+        assert!(later > start);
+        assert_eq!(later - start, Duration::from_secs(1));
+        assert_eq!(start - later, Duration::ZERO);
+        assert_eq!(start.checked_duration_since(later), None);
+        assert_eq!(later - Duration::from_secs(1), start);
+    }
+}
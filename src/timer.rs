@@ -0,0 +1,362 @@
+//! TIMER0 to TIMER4.
+//!
+//! The five timers share one register layout, so their registers are
+//! generated from the SVD once and [`Instance`] ties each timer to its banks,
+//! the same way the GPIO ports are tied to theirs. TIMER0 to TIMER2
+//! implement four capture/compare channels, TIMER3 and TIMER4 six.
+//!
+//! [`Timer`] drives the counter and its channels, [`Delay`] spends one timer
+//! on blocking waits measured in microseconds instead of CPU cycles.
+//!
+//! ```ignore
+//! let board = Board::take().unwrap();
+//! let mut delay = Delay::new(board.timer0);
+//! loop {
+//!     led.toggle();
+//!     delay.delay_ms(500);
+//! }
+//! ```
+use crate::{
+    Addressable, Error, ErrorKind, Interrupt, Read, ReadArray, Register, RegisterArray, Write,
+    WriteArray,
+};
+
+micro_macro::svd! { "svd/nrf52833.svd", TIMER0, TIMER1, TIMER2, TIMER3, TIMER4 }
+
+/// Number of `CC` registers in the register layout
+pub const CC_COUNT: usize = 6;
+/// Frequency of the clock the prescaler divides
+pub const BASE_FREQUENCY: u32 = 16_000_000;
+/// Largest prescaler, counting at [`BASE_FREQUENCY`] / 512
+pub const MAX_PRESCALER: u8 = 9;
+/// Prescaler counting once per microsecond
+const PRESCALER_1MHZ: u8 = 4;
+/// `COMPAREn` bits of `INTENSET`/`INTENCLR` for all channels
+const COMPARE_INTERRUPTS: u32 = 0x3F << 16;
+
+/// Registers and interrupt of one of the timers
+#[allow(non_camel_case_types)]
+pub trait Instance: Addressable {
+    /// Capture/compare channels the timer implements
+    const CHANNELS: usize;
+    const INTERRUPT: Interrupt;
+    type TASKS_START: TASKS_START + Register<Instance = Self>;
+    type TASKS_STOP: TASKS_STOP + Register<Instance = Self>;
+    type TASKS_CLEAR: TASKS_CLEAR + Register<Instance = Self>;
+    type TASKS_CAPTURE: TASKS_CAPTURE + RegisterArray<CC_COUNT, Instance = Self>;
+    type EVENTS_COMPARE: EVENTS_COMPARE + RegisterArray<CC_COUNT, Instance = Self>;
+    type SHORTS: SHORTS + Register<Instance = Self>;
+    type INTENSET: INTENSET + Register<Instance = Self>;
+    type INTENCLR: INTENCLR + Register<Instance = Self>;
+    type MODE: MODE + Register<Instance = Self>;
+    type BITMODE: BITMODE + Register<Instance = Self>;
+    type PRESCALER: PRESCALER + Register<Instance = Self>;
+    type CC: CC + RegisterArray<CC_COUNT, Instance = Self>;
+}
+macro_rules! def_instance {
+    ($($timer:ident, $n:literal, $channels:literal);+ $(;)?) => {
+        $(paste::paste! {
+            impl Instance for $timer {
+                const CHANNELS: usize = $channels;
+                const INTERRUPT: Interrupt = Interrupt::$timer;
+                type TASKS_START = [<TASKS_START $n>];
+                type TASKS_STOP = [<TASKS_STOP $n>];
+                type TASKS_CLEAR = [<TASKS_CLEAR $n>];
+                type TASKS_CAPTURE = [<TASKS_CAPTURE $n>];
+                type EVENTS_COMPARE = [<EVENTS_COMPARE $n>];
+                type SHORTS = [<SHORTS $n>];
+                type INTENSET = [<INTENSET $n>];
+                type INTENCLR = [<INTENCLR $n>];
+                type MODE = [<MODE $n>];
+                type BITMODE = [<BITMODE $n>];
+                type PRESCALER = [<PRESCALER $n>];
+                type CC = [<CC $n>];
+            }
+        })+
+    };
+}
+def_instance!(
+    TIMER0, 0, 4;
+    TIMER1, 1, 4;
+    TIMER2, 2, 4;
+    TIMER3, 3, 6;
+    TIMER4, 4, 6;
+);
+
+/// Width of the counter, which wraps to 0 after its largest value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bitmode {
+    Bits8,
+    Bits16,
+    Bits24,
+    Bits32,
+}
+
+/// Task a compare event triggers by itself on the timer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Short {
+    /// Restart counting from 0, for periodic events
+    Clear,
+    /// Stop counting, for one-shot events
+    Stop,
+}
+impl Short {
+    /// Bit of the short from the compare event of `channel` in `SHORTS`
+    const fn bit(self, channel: usize) -> u32 {
+        match self {
+            Short::Clear => 1 << channel,
+            Short::Stop => 1 << (8 + channel),
+        }
+    }
+}
+
+/// A timer counting up at a prescaled frequency, with its capture/compare
+/// channels
+///
+/// Methods taking a channel fail with [`ErrorKind::BadIndex`] for channels
+/// the timer doesn't implement.
+pub struct Timer<T: Instance> {
+    timer: T,
+}
+impl<T: Instance> Timer<T> {
+    /// Take `timer` and set it up stopped and cleared as a 32-bit timer
+    /// counting at 1 MHz, without shorts or interrupts
+    pub fn new(timer: T) -> Self {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+        T::MODE::write(|w| w.mode().timer());
+        T::BITMODE::write(|w| w.bitmode()._32bit());
+        T::PRESCALER::write(|w| w.prescaler().bits(PRESCALER_1MHZ as u32));
+        T::SHORTS::write(|w| w.bits(0));
+        T::INTENCLR::write(|w| w.bits(COMPARE_INTERRUPTS));
+        for channel in 0..T::CHANNELS {
+            T::EVENTS_COMPARE::write(channel, |w| w.events_compare().notgenerated());
+        }
+        T::TASKS_CLEAR::write(|w| w.tasks_clear().trigger());
+        Timer { timer }
+    }
+    /// Stop the timer, drop its shorts and interrupts and give it back
+    pub fn free(self) -> T {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+        T::SHORTS::write(|w| w.bits(0));
+        T::INTENCLR::write(|w| w.bits(COMPARE_INTERRUPTS));
+        self.timer
+    }
+
+    /// Start counting from the current value
+    #[inline]
+    pub fn start(&mut self) {
+        T::TASKS_START::write(|w| w.tasks_start().trigger());
+    }
+    /// Stop counting, keeping the current value
+    #[inline]
+    pub fn stop(&mut self) {
+        T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+    }
+    /// Set the counter back to 0, running or not
+    #[inline]
+    pub fn clear(&mut self) {
+        T::TASKS_CLEAR::write(|w| w.tasks_clear().trigger());
+    }
+
+    /// Count at [`BASE_FREQUENCY`] / 2^`prescaler`, taking effect once the
+    /// timer is stopped and started again
+    ///
+    /// Fails with [`ErrorKind::InvalidConfig`] above [`MAX_PRESCALER`].
+    pub fn set_prescaler(&mut self, prescaler: u8) -> Result<(), Error> {
+        if prescaler > MAX_PRESCALER {
+            return Err(ErrorKind::InvalidConfig.into());
+        }
+        T::PRESCALER::write(|w| w.prescaler().bits(prescaler as u32));
+        Ok(())
+    }
+    /// Counts per second at the current prescaler
+    pub fn frequency(&self) -> u32 {
+        BASE_FREQUENCY >> T::PRESCALER::read().prescaler().bits()
+    }
+    /// Change the width of the counter
+    pub fn set_bitmode(&mut self, bitmode: Bitmode) {
+        T::BITMODE::write(|w| match bitmode {
+            Bitmode::Bits8 => w.bitmode()._08bit(),
+            Bitmode::Bits16 => w.bitmode()._16bit(),
+            Bitmode::Bits24 => w.bitmode()._24bit(),
+            Bitmode::Bits32 => w.bitmode()._32bit(),
+        });
+    }
+
+    /// Copy the counter to `CC[channel]` and return it
+    pub fn capture(&mut self, channel: usize) -> Result<u32, Error> {
+        let channel = Self::check(channel)?;
+        T::TASKS_CAPTURE::write(channel, |w| w.tasks_capture().trigger());
+        Ok(T::CC::read(channel).cc().bits())
+    }
+    /// Raise the compare event of `channel` when the counter reaches `value`
+    pub fn set_compare(&mut self, channel: usize, value: u32) -> Result<(), Error> {
+        let channel = Self::check(channel)?;
+        T::CC::write(channel, |w| w.cc().bits(value));
+        Ok(())
+    }
+    /// Whether the counter has reached the compare value of `channel` since
+    /// the event was last reset
+    pub fn is_compare(&self, channel: usize) -> Result<bool, Error> {
+        let channel = Self::check(channel)?;
+        Ok(T::EVENTS_COMPARE::read(channel)
+            .events_compare()
+            .is_generated())
+    }
+    /// Reset the compare event of `channel`
+    pub fn reset_event(&mut self, channel: usize) -> Result<(), Error> {
+        let channel = Self::check(channel)?;
+        T::EVENTS_COMPARE::write(channel, |w| w.events_compare().notgenerated());
+        Ok(())
+    }
+    /// Busy-wait for the compare event of `channel` and reset it
+    pub fn wait(&mut self, channel: usize) -> Result<(), Error> {
+        while !self.is_compare(channel)? {}
+        self.reset_event(channel)
+    }
+
+    /// Interrupt on the compare event of `channel`
+    pub fn listen(&mut self, channel: usize) -> Result<(), Error> {
+        let channel = Self::check(channel)?;
+        T::INTENSET::write(|w| w.bits(1 << (16 + channel)));
+        Ok(())
+    }
+    /// Stop interrupting on the compare event of `channel`
+    pub fn unlisten(&mut self, channel: usize) -> Result<(), Error> {
+        let channel = Self::check(channel)?;
+        T::INTENCLR::write(|w| w.bits(1 << (16 + channel)));
+        Ok(())
+    }
+    /// Trigger `short` whenever the compare event of `channel` is raised
+    pub fn enable_short(&mut self, channel: usize, short: Short) -> Result<(), Error> {
+        let channel = Self::check(channel)?;
+        T::SHORTS::modify(|r, w| w.bits(r.bits() | short.bit(channel)));
+        Ok(())
+    }
+    /// Stop triggering `short` on the compare event of `channel`
+    pub fn disable_short(&mut self, channel: usize, short: Short) -> Result<(), Error> {
+        let channel = Self::check(channel)?;
+        T::SHORTS::modify(|r, w| w.bits(r.bits() & !short.bit(channel)));
+        Ok(())
+    }
+
+    #[inline]
+    fn check(channel: usize) -> Result<usize, Error> {
+        if channel < T::CHANNELS {
+            Ok(channel)
+        } else {
+            Err(ErrorKind::BadIndex.into())
+        }
+    }
+}
+
+/// Blocking delays timed by a timer counting at 1 MHz
+///
+/// Each delay runs the timer one-shot up to `CC[0]`, which stops it again.
+pub struct Delay<T: Instance> {
+    timer: Timer<T>,
+}
+impl<T: Instance> Delay<T> {
+    /// Take `timer` for delays
+    pub fn new(timer: T) -> Self {
+        let timer = Timer::new(timer);
+        T::SHORTS::write(|w| w.compare0_stop().enabled());
+        Delay { timer }
+    }
+    /// Give the timer back, stopped
+    pub fn free(self) -> T {
+        self.timer.free()
+    }
+    /// Wait for `us` microseconds
+    pub fn delay_us(&mut self, us: u32) {
+        if us == 0 {
+            return;
+        }
+        T::CC::write(0, |w| w.cc().bits(us));
+        self.timer.clear();
+        self.timer.start();
+        while T::EVENTS_COMPARE::read(0)
+            .events_compare()
+            .is_notgenerated()
+        {}
+        T::EVENTS_COMPARE::write(0, |w| w.events_compare().notgenerated());
+    }
+    /// Wait for `ms` milliseconds
+    pub fn delay_ms(&mut self, ms: u32) {
+        const MAX_MS: u32 = u32::MAX / 1000;
+        let mut left = ms;
+        while left > 0 {
+            let chunk = left.min(MAX_MS);
+            self.delay_us(chunk * 1000);
+            left -= chunk;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::sim;
+
+    #[test]
+    fn instances_use_their_own_banks() {
+        assert_eq!(<TIMER0 as Instance>::CC::ADDRS[0], 0x4000_8540);
+        assert_eq!(<TIMER3 as Instance>::CC::ADDRS[5], 0x4001_A554);
+        assert_eq!(<TIMER1 as Instance>::SHORTS::ADDR, 0x4000_9200);
+        assert_eq!(TIMER4::INTERRUPT, Interrupt::TIMER4);
+    }
+
+    #[test]
+    fn timer_configures_channels_shorts_and_interrupts() {
+        sim::reset();
+        let mut timer = Timer::new(TIMER3);
+        assert_eq!(sim::peek(BITMODE3::ADDR), 3);
+        assert_eq!(timer.frequency(), 1_000_000);
+        timer.set_prescaler(0).unwrap();
+        assert_eq!(timer.frequency(), BASE_FREQUENCY);
+        let error = timer.set_prescaler(10).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidConfig);
+        timer.set_bitmode(Bitmode::Bits24);
+        assert_eq!(sim::peek(BITMODE3::ADDR), 2);
+
+        timer.set_compare(5, 1234).unwrap();
+        assert_eq!(sim::peek(<TIMER3 as Instance>::CC::ADDRS[5]), 1234);
+        timer.enable_short(5, Short::Clear).unwrap();
+        timer.enable_short(1, Short::Stop).unwrap();
+        assert_eq!(sim::peek(SHORTS3::ADDR), 1 << 5 | 1 << 9);
+        timer.disable_short(5, Short::Clear).unwrap();
+        assert_eq!(sim::peek(SHORTS3::ADDR), 1 << 9);
+        timer.listen(2).unwrap();
+        assert_eq!(sim::peek(INTENSET3::ADDR), 1 << 18);
+
+        sim::poke(<TIMER3 as Instance>::EVENTS_COMPARE::ADDRS[2], 1);
+        assert!(timer.is_compare(2).unwrap());
+        timer.wait(2).unwrap();
+        assert!(!timer.is_compare(2).unwrap());
+
+        // TIMER0 to TIMER2 only implement four channels
+        let mut timer = Timer::new(TIMER0);
+        let error = timer.set_compare(4, 0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BadIndex);
+        assert!(timer.capture(3).is_ok());
+        let _ = timer.free();
+        assert_eq!(sim::peek(INTENCLR0::ADDR), COMPARE_INTERRUPTS);
+    }
+
+    #[test]
+    fn delay_runs_the_timer_one_shot() {
+        sim::reset();
+        let mut delay = Delay::new(TIMER1);
+        assert_eq!(sim::peek(SHORTS1::ADDR), 1 << 8);
+        assert_eq!(sim::peek(PRESCALER1::ADDR), 4);
+        sim::poke(EVENTS_COMPARE1::ADDRS[0], 1);
+        delay.delay_ms(3);
+        assert_eq!(sim::peek(<TIMER1 as Instance>::CC::ADDRS[0]), 3000);
+        assert_eq!(sim::peek(EVENTS_COMPARE1::ADDRS[0]), 0);
+        let starts = sim::writes()
+            .into_iter()
+            .filter(|&(addr, _)| addr == TASKS_START1::ADDR)
+            .count();
+        assert_eq!(starts, 1);
+    }
+}
@@ -0,0 +1,464 @@
+//! TWIM0 and TWIM1, the I2C masters with EasyDMA.
+//!
+//! Each transfer moves a whole buffer between RAM and the bus: the TWIM sends
+//! the address and the data, or reads into the buffer, and stops, suspends
+//! or carries on with the next transfer depending on its shortcuts. Every
+//! operation here is blocking and ends with a stop condition; the parts of a
+//! [`Twim::transaction`] are joined by repeated starts. A device holding the
+//! bus would block them forever, unless [`Twim::set_timeout`] bounds the
+//! waits by the monotonic clock.
+//!
+//! The TWIM shares its registers with the SPIM and SPIS of the same number,
+//! so only one of them can be enabled at a time.
+//!
+//! ```ignore
+//! let board = Board::take().unwrap();
+//! let mut i2c = Twim::new(
+//!     board.twim0,
+//!     board.i2c_internal.scl,
+//!     board.i2c_internal.sda,
+//!     Frequency::K400,
+//! );
+//! let mut id = [0];
+//! // WHO_AM_I of the accelerometer
+//! i2c.write_read(0x19, &[0x0F], &mut id)?;
+//! ```
+use crate::gpio::{Handle, OpenDrain, Output};
+use crate::mem::in_ram;
+use crate::time::{Deadline, Duration};
+use crate::{Addressable, Error, ErrorKind, Interrupt, Pin, Read, Register, Write};
+
+micro_macro::svd! { "svd/nrf52833.svd", TWIM0, TWIM1 }
+
+/// Most bytes a single EasyDMA transfer can move, the width of `MAXCNT`
+pub const MAX_TRANSFER: usize = 0xFFFF;
+/// Most bytes [`Twim::write`] copies to RAM when they live elsewhere
+pub const COPY_LIMIT: usize = 64;
+
+/// Registers and interrupt of one of the TWIMs
+#[allow(non_camel_case_types)]
+pub trait Instance: Addressable {
+    const INTERRUPT: Interrupt;
+    type TASKS_STARTRX: TASKS_STARTRX + Register<Instance = Self>;
+    type TASKS_STARTTX: TASKS_STARTTX + Register<Instance = Self>;
+    type TASKS_STOP: TASKS_STOP + Register<Instance = Self>;
+    type TASKS_RESUME: TASKS_RESUME + Register<Instance = Self>;
+    type EVENTS_STOPPED: EVENTS_STOPPED + Register<Instance = Self>;
+    type EVENTS_ERROR: EVENTS_ERROR + Register<Instance = Self>;
+    type EVENTS_SUSPENDED: EVENTS_SUSPENDED + Register<Instance = Self>;
+    type SHORTS: SHORTS + Register<Instance = Self>;
+    type ERRORSRC: ERRORSRC + Register<Instance = Self>;
+    type ENABLE: ENABLE + Register<Instance = Self>;
+    type PSEL_SCL: PSEL_SCL + Register<Instance = Self>;
+    type PSEL_SDA: PSEL_SDA + Register<Instance = Self>;
+    type FREQUENCY: FREQUENCY + Register<Instance = Self>;
+    type RXD_PTR: RXD_PTR + Register<Instance = Self>;
+    type RXD_MAXCNT: RXD_MAXCNT + Register<Instance = Self>;
+    type TXD_PTR: TXD_PTR + Register<Instance = Self>;
+    type TXD_MAXCNT: TXD_MAXCNT + Register<Instance = Self>;
+    type ADDRESS: ADDRESS + Register<Instance = Self>;
+}
+macro_rules! def_instance {
+    ($($twim:ident, $n:literal, $interrupt:ident);+ $(;)?) => {
+        $(paste::paste! {
+            impl Instance for $twim {
+                const INTERRUPT: Interrupt = Interrupt::$interrupt;
+                type TASKS_STARTRX = [<TASKS_STARTRX $n>];
+                type TASKS_STARTTX = [<TASKS_STARTTX $n>];
+                type TASKS_STOP = [<TASKS_STOP $n>];
+                type TASKS_RESUME = [<TASKS_RESUME $n>];
+                type EVENTS_STOPPED = [<EVENTS_STOPPED $n>];
+                type EVENTS_ERROR = [<EVENTS_ERROR $n>];
+                type EVENTS_SUSPENDED = [<EVENTS_SUSPENDED $n>];
+                type SHORTS = [<SHORTS $n>];
+                type ERRORSRC = [<ERRORSRC $n>];
+                type ENABLE = [<ENABLE $n>];
+                type PSEL_SCL = [<PSEL_SCL $n>];
+                type PSEL_SDA = [<PSEL_SDA $n>];
+                type FREQUENCY = [<FREQUENCY $n>];
+                type RXD_PTR = [<RXD_PTR $n>];
+                type RXD_MAXCNT = [<RXD_MAXCNT $n>];
+                type TXD_PTR = [<TXD_PTR $n>];
+                type TXD_MAXCNT = [<TXD_MAXCNT $n>];
+                type ADDRESS = [<ADDRESS $n>];
+            }
+        })+
+    };
+}
+def_instance!(
+    TWIM0, 0, SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0;
+    TWIM1, 1, SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1;
+);
+
+/// Clock frequency of the bus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    /// 100 kbps, standard mode
+    K100,
+    K250,
+    /// 400 kbps, fast mode
+    K400,
+}
+impl From<Frequency> for frequency::FREQUENCY_A {
+    fn from(frequency: Frequency) -> Self {
+        match frequency {
+            Frequency::K100 => frequency::FREQUENCY_A::K100,
+            Frequency::K250 => frequency::FREQUENCY_A::K250,
+            Frequency::K400 => frequency::FREQUENCY_A::K400,
+        }
+    }
+}
+
+/// One part of a [`Twim::transaction`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operation<'a> {
+    /// Send the bytes, which must be in RAM
+    Write(&'a [u8]),
+    /// Fill the buffer
+    Read(&'a mut [u8]),
+}
+
+/// What ends a transfer
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum End {
+    Stop,
+    Suspend,
+}
+
+/// Call `f` with `bytes`, or with a copy of them on the stack if EasyDMA
+/// can't reach them
+fn with_bytes_in_ram<F>(bytes: &[u8], f: F) -> Result<(), Error>
+where
+    F: FnOnce(&[u8]) -> Result<(), Error>,
+{
+    if in_ram(bytes) {
+        return f(bytes);
+    }
+    if bytes.len() > COPY_LIMIT {
+        return Err(ErrorKind::InvalidConfig.into());
+    }
+    let mut buffer = [0; COPY_LIMIT];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    f(&buffer[..bytes.len()])
+}
+
+/// A TWIM driving a clock and a data line
+pub struct Twim<T: Instance, SCL: Pin, SDA: Pin> {
+    twim: T,
+    pins: Pins<SCL, SDA>,
+    timeout: Option<Duration>,
+}
+/// The clock and data lines of a TWIM
+pub struct Pins<SCL: Pin, SDA: Pin> {
+    pub scl: Handle<SCL, Output<OpenDrain>>,
+    pub sda: Handle<SDA, Output<OpenDrain>>,
+}
+impl<T: Instance, SCL: Pin, SDA: Pin> Twim<T, SCL, SDA> {
+    /// Take `twim` and the `scl` and `sda` pins, route the TWIM to them and
+    /// enable it at `frequency`
+    ///
+    /// Both lines must be open-drain, with the bus pulled up externally; the
+    /// pins keep their configuration.
+    pub fn new(
+        twim: T,
+        scl: Handle<SCL, Output<OpenDrain>>,
+        sda: Handle<SDA, Output<OpenDrain>>,
+        frequency: Frequency,
+    ) -> Self {
+        T::ENABLE::write(|w| w.enable().disabled());
+        T::PSEL_SCL::write(|w| {
+            w.pin()
+                .bits(SCL::PIN_ID as u32)
+                .port()
+                .bits(SCL::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::PSEL_SDA::write(|w| {
+            w.pin()
+                .bits(SDA::PIN_ID as u32)
+                .port()
+                .bits(SDA::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::FREQUENCY::write(|w| w.frequency().variant(frequency.into()));
+        T::ENABLE::write(|w| w.enable().enabled());
+        Twim {
+            twim,
+            pins: Pins { scl, sda },
+            timeout: None,
+        }
+    }
+    /// Disable the TWIM and give it back along with its pins
+    pub fn free(self) -> (T, Pins<SCL, SDA>) {
+        T::ENABLE::write(|w| w.enable().disabled());
+        T::PSEL_SCL::write(|w| w.connect().disconnected());
+        T::PSEL_SDA::write(|w| w.connect().disconnected());
+        (self.twim, self.pins)
+    }
+    pub fn set_frequency(&mut self, frequency: Frequency) {
+        T::FREQUENCY::write(|w| w.frequency().variant(frequency.into()));
+    }
+    /// Give up on a transfer that hasn't ended after `timeout`, as measured
+    /// by [`time::now`](crate::time::now), or wait forever with `None`, the
+    /// default
+    ///
+    /// A transfer that times out is stopped and fails with
+    /// [`ErrorKind::Timeout`]. With a timeout set, every operation fails with
+    /// [`ErrorKind::InvalidConfig`] while no monotonic clock is running.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Send `bytes` to the device at the 7-bit `address`
+    ///
+    /// Bytes outside RAM are copied to the stack first, failing with
+    /// [`ErrorKind::InvalidConfig`] if there are more than [`COPY_LIMIT`].
+    pub fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Error> {
+        with_bytes_in_ram(bytes, |bytes| {
+            self.transaction(address, &mut [Operation::Write(bytes)])
+        })
+    }
+    /// Fill `buffer` from the device at the 7-bit `address`
+    pub fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.transaction(address, &mut [Operation::Read(buffer)])
+    }
+    /// Send `bytes`, then fill `buffer` after a repeated start, the usual
+    /// way of reading a device's registers
+    ///
+    /// `bytes` are copied to RAM as by [`write`](Self::write).
+    pub fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        with_bytes_in_ram(bytes, |bytes| {
+            self.transaction(
+                address,
+                &mut [Operation::Write(bytes), Operation::Read(buffer)],
+            )
+        })
+    }
+    /// Run `operations` back to back with the device at the 7-bit `address`,
+    /// joined by repeated starts and ended by a stop
+    ///
+    /// Fails with [`ErrorKind::BusNack`] if the device didn't acknowledge its
+    /// address or a byte, [`ErrorKind::Overrun`] if a received byte was lost
+    /// and [`ErrorKind::InvalidConfig`] if `address` doesn't fit in 7 bits, a
+    /// buffer to send isn't in RAM, a buffer to fill is empty or any buffer
+    /// is longer than [`MAX_TRANSFER`], or with
+    /// [`ErrorKind::Timeout`] as set by [`set_timeout`](Self::set_timeout).
+    pub fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        if address > 0x7F {
+            return Err(ErrorKind::InvalidConfig.into());
+        }
+        for operation in operations.iter() {
+            let valid = match operation {
+                Operation::Write(bytes) => {
+                    (bytes.is_empty() || in_ram(bytes)) && bytes.len() <= MAX_TRANSFER
+                }
+                // The TWIM has no way to receive nothing, MAXCNT must be one
+                // or more
+                Operation::Read(buffer) => !buffer.is_empty() && buffer.len() <= MAX_TRANSFER,
+            };
+            if !valid {
+                return Err(ErrorKind::InvalidConfig.into());
+            }
+        }
+        T::ADDRESS::write(|w| w.address().bits(address as u32));
+        let last = operations.len().saturating_sub(1);
+        for (index, operation) in operations.iter_mut().enumerate() {
+            let end = if index == last {
+                End::Stop
+            } else {
+                End::Suspend
+            };
+            Self::transfer(operation, end, index > 0, self.timeout)?;
+        }
+        Ok(())
+    }
+
+    /// Run one transfer, resuming from the suspended state after the first
+    fn transfer(
+        operation: &mut Operation<'_>,
+        end: End,
+        resume: bool,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        let deadline = Deadline::after(timeout)?;
+        match operation {
+            Operation::Write(bytes) => {
+                T::TXD_PTR::write(|w| w.ptr().bits(bytes.as_ptr() as u32));
+                T::TXD_MAXCNT::write(|w| w.maxcnt().bits(bytes.len() as u32));
+                T::SHORTS::write(|w| match end {
+                    End::Stop => w.lasttx_stop().enabled(),
+                    End::Suspend => w.lasttx_suspend().enabled(),
+                });
+                T::TASKS_STARTTX::write(|w| w.tasks_starttx().trigger());
+            }
+            Operation::Read(buffer) => {
+                T::RXD_PTR::write(|w| w.ptr().bits(buffer.as_mut_ptr() as u32));
+                T::RXD_MAXCNT::write(|w| w.maxcnt().bits(buffer.len() as u32));
+                T::SHORTS::write(|w| match end {
+                    End::Stop => w.lastrx_stop().enabled(),
+                    End::Suspend => w.lastrx_suspend().enabled(),
+                });
+                T::TASKS_STARTRX::write(|w| w.tasks_startrx().trigger());
+            }
+        }
+        if resume {
+            T::TASKS_RESUME::write(|w| w.tasks_resume().trigger());
+        }
+        Self::wait(end, deadline, timeout)
+    }
+    /// Wait for the transfer to end in `end` or an error until `deadline`,
+    /// stopping the bus on an error or once the deadline has passed
+    ///
+    /// The bus gets `timeout` once more to stop.
+    fn wait(end: End, deadline: Deadline, timeout: Option<Duration>) -> Result<(), Error> {
+        let mut failed = false;
+        let waited = deadline.wait(|| {
+            failed = T::EVENTS_ERROR::read().events_error().is_generated();
+            failed
+                || match end {
+                    End::Stop => T::EVENTS_STOPPED::read().events_stopped().is_generated(),
+                    End::Suspend => T::EVENTS_SUSPENDED::read()
+                        .events_suspended()
+                        .is_generated(),
+                }
+        });
+        if failed || waited.is_err() {
+            T::EVENTS_ERROR::write(|w| w.events_error().notgenerated());
+            T::TASKS_RESUME::write(|w| w.tasks_resume().trigger());
+            T::TASKS_STOP::write(|w| w.tasks_stop().trigger());
+            let stopped = Deadline::after(timeout)?
+                .wait(|| T::EVENTS_STOPPED::read().events_stopped().is_generated());
+            T::EVENTS_STOPPED::write(|w| w.events_stopped().notgenerated());
+            waited?;
+            stopped?;
+            return Self::take_error();
+        }
+        match end {
+            End::Stop => T::EVENTS_STOPPED::write(|w| w.events_stopped().notgenerated()),
+            End::Suspend => T::EVENTS_SUSPENDED::write(|w| w.events_suspended().notgenerated()),
+        }
+        Self::take_error()
+    }
+    /// The error of the last transfer, clearing it
+    fn take_error() -> Result<(), Error> {
+        let source = T::ERRORSRC::read();
+        if source.bits() == 0 {
+            return Ok(());
+        }
+        // Write one to clear
+        T::ERRORSRC::write(|w| w.bits(source.bits()));
+        if source.overrun().is_received() {
+            Err(ErrorKind::Overrun.into())
+        } else {
+            Err(ErrorKind::BusNack.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::mem::sim;
+    use crate::rtc::{Monotonic, RTC2};
+    use crate::{Peripherals, P008, P016};
+
+    fn i2c() -> Twim<TWIM0, P008, P016> {
+        sim::reset();
+        let board = Board::new(unsafe { Peripherals::steal() });
+        let pins = board.i2c_internal;
+        Twim::new(board.twim0, pins.scl, pins.sda, Frequency::K400)
+    }
+
+    #[test]
+    fn pins_and_frequency_are_configured() {
+        let _i2c = i2c();
+        // P0.08 and P0.16, connected
+        assert_eq!(sim::peek(PSEL_SCL0::ADDR), 8);
+        assert_eq!(sim::peek(PSEL_SDA0::ADDR), 16);
+        assert_eq!(sim::peek(FREQUENCY0::ADDR), 0x0640_0000);
+        assert_eq!(sim::peek(ENABLE0::ADDR), 6);
+        assert_eq!(<TWIM1 as Instance>::ADDRESS::ADDR, 0x4000_4588);
+    }
+
+    #[test]
+    fn free_gives_the_pins_back() {
+        let (_, pins) = i2c().free();
+        assert_eq!(sim::peek(ENABLE0::ADDR), 0);
+        assert_eq!(sim::peek(PSEL_SCL0::ADDR), 1 << 31);
+        assert!(pins.scl.is_set_high());
+    }
+
+    #[test]
+    fn write_read_uses_a_repeated_start() {
+        let mut i2c = i2c();
+        let mut buffer = [0; 6];
+        sim::poke(EVENTS_SUSPENDED0::ADDR, 1);
+        sim::poke(EVENTS_STOPPED0::ADDR, 1);
+        i2c.write_read(0x19, &[0x0F], &mut buffer).unwrap();
+        assert_eq!(sim::peek(ADDRESS0::ADDR), 0x19);
+        assert_eq!(sim::peek(TXD_MAXCNT0::ADDR), 1);
+        assert_eq!(sim::peek(RXD_MAXCNT0::ADDR), 6);
+        assert_eq!(sim::peek(RXD_PTR0::ADDR), buffer.as_ptr() as u32);
+        // The write suspends, the read resumes and stops
+        let shorts: Vec<_> = sim::writes()
+            .into_iter()
+            .filter(|(addr, _)| *addr == SHORTS0::ADDR)
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(shorts, [1 << 8, 1 << 12]);
+        assert_eq!(sim::peek(TASKS_RESUME0::ADDR), 1);
+    }
+
+    #[test]
+    fn nack_and_overrun_are_reported() {
+        let mut i2c = i2c();
+        sim::poke(EVENTS_ERROR0::ADDR, 1);
+        sim::poke(EVENTS_STOPPED0::ADDR, 1);
+        sim::poke(ERRORSRC0::ADDR, 1 << 1);
+        let error = i2c.read(0x19, &mut [0; 2]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BusNack);
+        assert_eq!(sim::peek(TASKS_STOP0::ADDR), 1);
+        sim::poke(EVENTS_STOPPED0::ADDR, 1);
+        sim::poke(ERRORSRC0::ADDR, 1);
+        let error = i2c.read(0x19, &mut [0; 2]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Overrun);
+    }
+
+    #[test]
+    fn stuck_transfers_time_out() {
+        let mut i2c = i2c();
+        let _clock = Monotonic::new(RTC2);
+        i2c.set_timeout(Some(Duration::ZERO));
+        // Time stands still in the simulation, so only an empty wait expires
+        let error = i2c.read(0x19, &mut [0; 2]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert_eq!(sim::peek(TASKS_STOP0::ADDR), 1);
+    }
+
+    #[test]
+    fn invalid_transfers_are_rejected() {
+        let mut i2c = i2c();
+        let error = i2c.write(0x19, &vec![0; MAX_TRANSFER + 1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidConfig);
+        let error = i2c.read(0x19, &mut []).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidConfig);
+        let error = i2c.write(0x80, &[0]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidConfig);
+        assert_eq!(sim::peek(ADDRESS0::ADDR), 0);
+        assert!(sim::writes()
+            .iter()
+            .all(|(addr, _)| *addr != TASKS_STARTTX0::ADDR));
+    }
+}
@@ -0,0 +1,760 @@
+//! UARTE0 and UARTE1, the UARTs with EasyDMA.
+//!
+//! The UARTE moves data between its line and RAM on its own: a transfer is
+//! started with a pointer and a length and ends with `ENDTX`/`ENDRX`. The
+//! blocking [`Uarte::write`] and [`Uarte::read`] wait for that themselves;
+//! [`Uarte::start_write`] and [`Uarte::start_read`] hand a `'static` buffer to
+//! the peripheral and return straight away, the buffer coming back from
+//! [`Uarte::finish_write`]/[`Uarte::finish_read`] once the transfer is done.
+//! A line that never delivers holds the blocking ones forever, unless
+//! [`Uarte::set_timeout`] bounds them by the monotonic clock.
+//!
+//! EasyDMA can only reach RAM, so `write` copies data that lives in flash,
+//! string literals for instance, through a small buffer on the stack first.
+//!
+//! ```ignore
+//! let mut board = Board::take().unwrap();
+//! // The monotonic clock bounds `read_within`
+//! board.clock.start_lfclk(LfclkSource::Rc, None)?;
+//! let _clock = Monotonic::new(board.rtc1);
+//! let mut serial = Uarte::new(
+//!     board.uarte0,
+//!     board.uart.tx,
+//!     board.uart.rx,
+//!     Config::default(),
+//! );
+//! serial.write(b"hello\r\n").unwrap();
+//! let mut line = [0; 8];
+//! let received = serial.read_within(&mut line, Duration::from_secs(1))?;
+//! ```
+use crate::gpio::{Handle, Input, Output, PushPull};
+use crate::mem::in_ram;
+use crate::time::{Deadline, Duration};
+use crate::{Addressable, Error, ErrorKind, Interrupt, Pin, Read, Register, Write};
+
+micro_macro::svd! { "svd/nrf52833.svd", UARTE0, UARTE1 }
+
+/// Most bytes a single EasyDMA transfer can move, the width of `MAXCNT`
+pub const MAX_TRANSFER: usize = 0xFFFF;
+/// Size of the stack buffer data outside RAM is copied through
+const COPY_CHUNK: usize = 32;
+
+/// Registers and interrupt of one of the UARTEs
+#[allow(non_camel_case_types)]
+pub trait Instance: Addressable {
+    const INTERRUPT: Interrupt;
+    type TASKS_STARTRX: TASKS_STARTRX + Register<Instance = Self>;
+    type TASKS_STOPRX: TASKS_STOPRX + Register<Instance = Self>;
+    type TASKS_STARTTX: TASKS_STARTTX + Register<Instance = Self>;
+    type TASKS_STOPTX: TASKS_STOPTX + Register<Instance = Self>;
+    type EVENTS_ENDRX: EVENTS_ENDRX + Register<Instance = Self>;
+    type EVENTS_ENDTX: EVENTS_ENDTX + Register<Instance = Self>;
+    type EVENTS_ERROR: EVENTS_ERROR + Register<Instance = Self>;
+    type EVENTS_RXTO: EVENTS_RXTO + Register<Instance = Self>;
+    type EVENTS_TXSTOPPED: EVENTS_TXSTOPPED + Register<Instance = Self>;
+    type INTENSET: INTENSET + Register<Instance = Self>;
+    type INTENCLR: INTENCLR + Register<Instance = Self>;
+    type ERRORSRC: ERRORSRC + Register<Instance = Self>;
+    type ENABLE: ENABLE + Register<Instance = Self>;
+    type PSEL_RTS: PSEL_RTS + Register<Instance = Self>;
+    type PSEL_TXD: PSEL_TXD + Register<Instance = Self>;
+    type PSEL_CTS: PSEL_CTS + Register<Instance = Self>;
+    type PSEL_RXD: PSEL_RXD + Register<Instance = Self>;
+    type BAUDRATE: BAUDRATE + Register<Instance = Self>;
+    type RXD_PTR: RXD_PTR + Register<Instance = Self>;
+    type RXD_MAXCNT: RXD_MAXCNT + Register<Instance = Self>;
+    type RXD_AMOUNT: RXD_AMOUNT + Register<Instance = Self>;
+    type TXD_PTR: TXD_PTR + Register<Instance = Self>;
+    type TXD_MAXCNT: TXD_MAXCNT + Register<Instance = Self>;
+    type CONFIG: CONFIG + Register<Instance = Self>;
+}
+macro_rules! def_instance {
+    ($($uarte:ident, $n:literal, $interrupt:ident);+ $(;)?) => {
+        $(paste::paste! {
+            impl Instance for $uarte {
+                const INTERRUPT: Interrupt = Interrupt::$interrupt;
+                type TASKS_STARTRX = [<TASKS_STARTRX $n>];
+                type TASKS_STOPRX = [<TASKS_STOPRX $n>];
+                type TASKS_STARTTX = [<TASKS_STARTTX $n>];
+                type TASKS_STOPTX = [<TASKS_STOPTX $n>];
+                type EVENTS_ENDRX = [<EVENTS_ENDRX $n>];
+                type EVENTS_ENDTX = [<EVENTS_ENDTX $n>];
+                type EVENTS_ERROR = [<EVENTS_ERROR $n>];
+                type EVENTS_RXTO = [<EVENTS_RXTO $n>];
+                type EVENTS_TXSTOPPED = [<EVENTS_TXSTOPPED $n>];
+                type INTENSET = [<INTENSET $n>];
+                type INTENCLR = [<INTENCLR $n>];
+                type ERRORSRC = [<ERRORSRC $n>];
+                type ENABLE = [<ENABLE $n>];
+                type PSEL_RTS = [<PSEL_RTS $n>];
+                type PSEL_TXD = [<PSEL_TXD $n>];
+                type PSEL_CTS = [<PSEL_CTS $n>];
+                type PSEL_RXD = [<PSEL_RXD $n>];
+                type BAUDRATE = [<BAUDRATE $n>];
+                type RXD_PTR = [<RXD_PTR $n>];
+                type RXD_MAXCNT = [<RXD_MAXCNT $n>];
+                type RXD_AMOUNT = [<RXD_AMOUNT $n>];
+                type TXD_PTR = [<TXD_PTR $n>];
+                type TXD_MAXCNT = [<TXD_MAXCNT $n>];
+                type CONFIG = [<CONFIG $n>];
+            }
+        })+
+    };
+}
+def_instance!(
+    UARTE0, 0, UARTE0_UART0;
+    UARTE1, 1, UARTE1;
+);
+
+/// Symbol rate of the line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Baudrate {
+    Baud1200,
+    Baud2400,
+    Baud4800,
+    Baud9600,
+    Baud14400,
+    Baud19200,
+    Baud28800,
+    Baud31250,
+    Baud38400,
+    Baud56000,
+    Baud57600,
+    Baud76800,
+    Baud115200,
+    Baud230400,
+    Baud250000,
+    Baud460800,
+    Baud921600,
+    Baud1M,
+}
+impl From<Baudrate> for baudrate::BAUDRATE_A {
+    fn from(baudrate: Baudrate) -> Self {
+        match baudrate {
+            Baudrate::Baud1200 => baudrate::BAUDRATE_A::Baud1200,
+            Baudrate::Baud2400 => baudrate::BAUDRATE_A::Baud2400,
+            Baudrate::Baud4800 => baudrate::BAUDRATE_A::Baud4800,
+            Baudrate::Baud9600 => baudrate::BAUDRATE_A::Baud9600,
+            Baudrate::Baud14400 => baudrate::BAUDRATE_A::Baud14400,
+            Baudrate::Baud19200 => baudrate::BAUDRATE_A::Baud19200,
+            Baudrate::Baud28800 => baudrate::BAUDRATE_A::Baud28800,
+            Baudrate::Baud31250 => baudrate::BAUDRATE_A::Baud31250,
+            Baudrate::Baud38400 => baudrate::BAUDRATE_A::Baud38400,
+            Baudrate::Baud56000 => baudrate::BAUDRATE_A::Baud56000,
+            Baudrate::Baud57600 => baudrate::BAUDRATE_A::Baud57600,
+            Baudrate::Baud76800 => baudrate::BAUDRATE_A::Baud76800,
+            Baudrate::Baud115200 => baudrate::BAUDRATE_A::Baud115200,
+            Baudrate::Baud230400 => baudrate::BAUDRATE_A::Baud230400,
+            Baudrate::Baud250000 => baudrate::BAUDRATE_A::Baud250000,
+            Baudrate::Baud460800 => baudrate::BAUDRATE_A::Baud460800,
+            Baudrate::Baud921600 => baudrate::BAUDRATE_A::Baud921600,
+            Baudrate::Baud1M => baudrate::BAUDRATE_A::Baud1m,
+        }
+    }
+}
+
+/// Parity bit sent after the data bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopBits {
+    One,
+    Two,
+}
+
+/// Line settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub baudrate: Baudrate,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+}
+/// 115200 baud, no parity, one stop bit, what the interface chip expects
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            baudrate: Baudrate::Baud115200,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+/// Events that can raise the interrupt of the UARTE
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A transfer started with [`Uarte::start_read`] has ended
+    EndRx,
+    /// A transfer started with [`Uarte::start_write`] has ended
+    EndTx,
+    /// A line error was detected while receiving
+    Error,
+    /// The receiver has stopped after [`Uarte::stop_read`]
+    RxTimeout,
+}
+impl Event {
+    /// Bit of the event in `INTENSET`/`INTENCLR`
+    fn bit(self) -> u32 {
+        match self {
+            Event::EndRx => 1 << 4,
+            Event::EndTx => 1 << 8,
+            Event::Error => 1 << 9,
+            Event::RxTimeout => 1 << 17,
+        }
+    }
+}
+
+/// A UARTE driving a pair of pins
+pub struct Uarte<T: Instance, TX: Pin, RX: Pin, PULL> {
+    uarte: T,
+    pins: Pins<TX, RX, PULL>,
+    tx: Option<&'static [u8]>,
+    rx: Option<&'static mut [u8]>,
+    timeout: Option<Duration>,
+    /// Whether the transmitter was started since it last reported `TXSTOPPED`
+    tx_started: bool,
+    /// Whether the receiver was started since it last reported `RXTO`
+    rx_started: bool,
+}
+/// The transmit and receive lines of a UARTE
+pub struct Pins<TX: Pin, RX: Pin, PULL> {
+    pub tx: Handle<TX, Output<PushPull>>,
+    pub rx: Handle<RX, Input<PULL>>,
+}
+impl<T: Instance, TX: Pin, RX: Pin, PULL> Uarte<T, TX, RX, PULL> {
+    /// Take `uarte` and the `tx` and `rx` pins, route the UARTE to them and
+    /// enable it with `config`
+    ///
+    /// The pins keep their configuration; `tx` should idle high.
+    pub fn new(
+        uarte: T,
+        tx: Handle<TX, Output<PushPull>>,
+        rx: Handle<RX, Input<PULL>>,
+        config: Config,
+    ) -> Self {
+        T::ENABLE::write(|w| w.enable().disabled());
+        T::PSEL_TXD::write(|w| {
+            w.pin()
+                .bits(TX::PIN_ID as u32)
+                .port()
+                .bits(TX::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::PSEL_RXD::write(|w| {
+            w.pin()
+                .bits(RX::PIN_ID as u32)
+                .port()
+                .bits(RX::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::PSEL_CTS::write(|w| w.connect().disconnected());
+        T::PSEL_RTS::write(|w| w.connect().disconnected());
+        let mut serial = Uarte {
+            uarte,
+            pins: Pins { tx, rx },
+            tx: None,
+            rx: None,
+            timeout: None,
+            tx_started: false,
+            rx_started: false,
+        };
+        serial.set_config(config);
+        T::ENABLE::write(|w| w.enable().enabled());
+        serial
+    }
+    /// Stop any transfer, disable the UARTE and give it back along with its
+    /// pins
+    ///
+    /// Transfers started in the background are cut short and their buffers
+    /// dropped. The UARTE is only disabled once the transmitter and the
+    /// receiver, those of them that were started, have reported that they
+    /// stopped, since disabling it mid-transfer leaves it drawing current.
+    /// That wait is bounded by [`set_timeout`](Self::set_timeout), after
+    /// which, or straight away with a timeout but no clock to measure it,
+    /// the UARTE is disabled regardless.
+    pub fn free(mut self) -> (T, Pins<TX, RX, PULL>) {
+        T::INTENCLR::write(|w| w.bits(u32::MAX));
+        self.tx = None;
+        self.rx = None;
+        // A direction that never started never reports that it stopped
+        let (tx, rx) = (self.tx_started, self.rx_started);
+        if tx {
+            T::TASKS_STOPTX::write(|w| w.tasks_stoptx().trigger());
+        }
+        if rx {
+            T::TASKS_STOPRX::write(|w| w.tasks_stoprx().trigger());
+        }
+        if let Ok(deadline) = Deadline::after(self.timeout) {
+            let _ = deadline.wait(|| {
+                (!tx || T::EVENTS_TXSTOPPED::read()
+                    .events_txstopped()
+                    .is_generated())
+                    && (!rx || T::EVENTS_RXTO::read().events_rxto().is_generated())
+            });
+        }
+        T::EVENTS_TXSTOPPED::write(|w| w.events_txstopped().notgenerated());
+        T::EVENTS_RXTO::write(|w| w.events_rxto().notgenerated());
+        T::ENABLE::write(|w| w.enable().disabled());
+        T::PSEL_TXD::write(|w| w.connect().disconnected());
+        T::PSEL_RXD::write(|w| w.connect().disconnected());
+        T::PSEL_CTS::write(|w| w.connect().disconnected());
+        T::PSEL_RTS::write(|w| w.connect().disconnected());
+        (self.uarte, self.pins)
+    }
+    /// Change the baud rate, parity and stop bits, keeping flow control as
+    /// it is
+    pub fn set_config(&mut self, config: Config) {
+        T::BAUDRATE::write(|w| w.baudrate().variant(config.baudrate.into()));
+        T::CONFIG::modify(|_, w| {
+            let w = match config.parity {
+                Parity::None => w.parity().excluded(),
+                Parity::Even => w.parity().included().paritytype().even(),
+                Parity::Odd => w.parity().included().paritytype().odd(),
+            };
+            match config.stop_bits {
+                StopBits::One => w.stop().one(),
+                StopBits::Two => w.stop().two(),
+            }
+        });
+    }
+    /// Give up on a blocking [`write`](Self::write) or [`read`](Self::read),
+    /// or the wait of [`finish_write`](Self::finish_write) or
+    /// [`finish_read`](Self::finish_read), that hasn't ended after `timeout`,
+    /// as measured by [`time::now`](crate::time::now), or wait forever with
+    /// `None`, the default
+    ///
+    /// A transfer that times out is stopped and fails with
+    /// [`ErrorKind::Timeout`]. With a timeout set, they fail with
+    /// [`ErrorKind::InvalidConfig`] while no monotonic clock is running.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+    /// Hold off sending while `cts` is high and raise `rts` while the
+    /// receiver can't take more data
+    pub fn enable_flow_control<CTS: Pin, RTS: Pin, CTSPULL>(
+        &mut self,
+        _cts: &Handle<CTS, Input<CTSPULL>>,
+        _rts: &Handle<RTS, Output<PushPull>>,
+    ) {
+        T::ENABLE::write(|w| w.enable().disabled());
+        T::PSEL_CTS::write(|w| {
+            w.pin()
+                .bits(CTS::PIN_ID as u32)
+                .port()
+                .bits(CTS::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::PSEL_RTS::write(|w| {
+            w.pin()
+                .bits(RTS::PIN_ID as u32)
+                .port()
+                .bits(RTS::PORT_ID as u32)
+                .connect()
+                .connected()
+        });
+        T::CONFIG::modify(|_, w| w.hwfc().enabled());
+        T::ENABLE::write(|w| w.enable().enabled());
+    }
+    /// Stop using the flow control pins
+    pub fn disable_flow_control(&mut self) {
+        T::CONFIG::modify(|_, w| w.hwfc().disabled());
+        T::PSEL_CTS::write(|w| w.connect().disconnected());
+        T::PSEL_RTS::write(|w| w.connect().disconnected());
+    }
+
+    /// Send `bytes`, returning once the last one has been handed to the line
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let _ = self.finish_write();
+        if in_ram(bytes) {
+            for chunk in bytes.chunks(MAX_TRANSFER) {
+                self.transmit(chunk)?;
+            }
+        } else {
+            let mut buffer = [0; COPY_CHUNK];
+            for chunk in bytes.chunks(COPY_CHUNK) {
+                buffer[..chunk.len()].copy_from_slice(chunk);
+                self.transmit(&buffer[..chunk.len()])?;
+            }
+        }
+        Ok(())
+    }
+    /// Receive exactly `buffer.len()` bytes
+    ///
+    /// Fails with [`ErrorKind::Overrun`] if bytes were lost and
+    /// [`ErrorKind::Framing`] or [`ErrorKind::Parity`] if one arrived
+    /// garbled, after filling the buffer either way, or with
+    /// [`ErrorKind::Timeout`] as set by [`set_timeout`](Self::set_timeout).
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        for chunk in buffer.chunks_mut(MAX_TRANSFER) {
+            let deadline = Deadline::after(self.timeout)?;
+            self.start_rx(chunk.as_mut_ptr(), chunk.len());
+            let ended = deadline.wait(|| T::EVENTS_ENDRX::read().events_endrx().is_generated());
+            if ended.is_err() {
+                self.stop_rx()?;
+            }
+            T::EVENTS_ENDRX::write(|w| w.events_endrx().notgenerated());
+            ended?;
+            Self::take_error()?;
+        }
+        Ok(())
+    }
+    /// Receive up to `buffer.len()` bytes, giving up after `timeout` as
+    /// measured by [`time::now`](crate::time::now)
+    ///
+    /// Returns how many bytes arrived, fewer than asked for if the time ran
+    /// out, or [`ErrorKind::Timeout`] if none did. Fails with
+    /// [`ErrorKind::InvalidConfig`] if no monotonic clock is running.
+    pub fn read_within(&mut self, buffer: &mut [u8], timeout: Duration) -> Result<usize, Error> {
+        let deadline = Deadline::after(Some(timeout))?;
+        let len = buffer.len().min(MAX_TRANSFER);
+        self.start_rx(buffer.as_mut_ptr(), len);
+        let ended = || T::EVENTS_ENDRX::read().events_endrx().is_generated();
+        while !ended() && !deadline.expired() {}
+        if !ended() {
+            // Stopping ends the transfer with what has arrived so far
+            self.stop_rx()?;
+        }
+        T::EVENTS_ENDRX::write(|w| w.events_endrx().notgenerated());
+        Self::take_error()?;
+        match T::RXD_AMOUNT::read().amount().bits() as usize {
+            0 => Err(ErrorKind::Timeout.into()),
+            received => Ok(received),
+        }
+    }
+
+    /// Start sending `buffer` in the background, after waiting for any
+    /// transfer started earlier
+    ///
+    /// Panics if `buffer` is outside RAM or longer than [`MAX_TRANSFER`].
+    pub fn start_write(&mut self, buffer: &'static [u8]) {
+        assert!(in_ram(buffer), "EasyDMA can only read from RAM");
+        assert!(buffer.len() <= MAX_TRANSFER, "transfer too long");
+        let _ = self.finish_write();
+        self.start_tx(buffer);
+        self.tx = Some(buffer);
+    }
+    /// Whether the transfer started with [`start_write`](Self::start_write)
+    /// has ended, `true` if there is none
+    pub fn is_write_done(&self) -> bool {
+        self.tx.is_none() || T::EVENTS_ENDTX::read().events_endtx().is_generated()
+    }
+    /// Wait for the transfer started with [`start_write`](Self::start_write)
+    /// to end and get its buffer back, `None` if there is none
+    ///
+    /// A transfer that hasn't ended within the timeout set by
+    /// [`set_timeout`](Self::set_timeout) is stopped and reported as
+    /// [`ErrorKind::Timeout`].
+    pub fn finish_write(&mut self) -> Option<(&'static [u8], Result<(), Error>)> {
+        let buffer = self.tx.take()?;
+        let result = self.end_tx();
+        Some((buffer, result))
+    }
+    /// Start receiving into `buffer` in the background, after waiting for
+    /// any transfer started earlier
+    ///
+    /// Panics if `buffer` is longer than [`MAX_TRANSFER`].
+    pub fn start_read(&mut self, buffer: &'static mut [u8]) {
+        assert!(buffer.len() <= MAX_TRANSFER, "transfer too long");
+        let _ = self.finish_read();
+        self.start_rx(buffer.as_mut_ptr(), buffer.len());
+        self.rx = Some(buffer);
+    }
+    /// Whether the transfer started with [`start_read`](Self::start_read)
+    /// has ended, `true` if there is none
+    pub fn is_read_done(&self) -> bool {
+        self.rx.is_none() || T::EVENTS_ENDRX::read().events_endrx().is_generated()
+    }
+    /// End the transfer started with [`start_read`](Self::start_read) early,
+    /// keeping what has arrived so far
+    pub fn stop_read(&mut self) {
+        if self.rx.is_some() {
+            T::TASKS_STOPRX::write(|w| w.tasks_stoprx().trigger());
+        }
+    }
+    /// Wait for the transfer started with [`start_read`](Self::start_read)
+    /// to end and get its buffer back along with how many bytes arrived, or
+    /// the line error that occurred; `None` if there is no transfer
+    ///
+    /// A transfer that hasn't ended within the timeout set by
+    /// [`set_timeout`](Self::set_timeout) is stopped and reported as
+    /// [`ErrorKind::Timeout`].
+    pub fn finish_read(&mut self) -> Option<(&'static mut [u8], Result<usize, Error>)> {
+        let buffer = self.rx.take()?;
+        let ended = Deadline::after(self.timeout).and_then(|deadline| {
+            deadline.wait(|| T::EVENTS_ENDRX::read().events_endrx().is_generated())
+        });
+        if ended.is_err() {
+            // Stopping ends the transfer, so EasyDMA lets go of the buffer
+            let _ = self.stop_rx();
+        }
+        T::EVENTS_ENDRX::write(|w| w.events_endrx().notgenerated());
+        if T::EVENTS_RXTO::read().events_rxto().is_generated() {
+            // Stopped by `stop_read`
+            T::EVENTS_RXTO::write(|w| w.events_rxto().notgenerated());
+            self.rx_started = false;
+        }
+        let result = ended
+            .and_then(|()| Self::take_error())
+            .map(|()| T::RXD_AMOUNT::read().amount().bits() as usize);
+        Some((buffer, result))
+    }
+
+    /// Raise the interrupt of the UARTE on `event`
+    pub fn listen(&mut self, event: Event) {
+        T::INTENSET::write(|w| w.bits(event.bit()));
+    }
+    /// Stop `event` from raising the interrupt
+    pub fn unlisten(&mut self, event: Event) {
+        T::INTENCLR::write(|w| w.bits(event.bit()));
+    }
+
+    /// Send `bytes` and wait for them to go out
+    fn transmit(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.start_tx(bytes);
+        self.end_tx()
+    }
+    fn start_tx(&mut self, bytes: &[u8]) {
+        // So only a stop after this transfer counts in `free`
+        T::EVENTS_TXSTOPPED::write(|w| w.events_txstopped().notgenerated());
+        self.tx_started = true;
+        T::TXD_PTR::write(|w| w.ptr().bits(bytes.as_ptr() as u32));
+        T::TXD_MAXCNT::write(|w| w.maxcnt().bits(bytes.len() as u32));
+        T::TASKS_STARTTX::write(|w| w.tasks_starttx().trigger());
+    }
+    /// Wait up to the timeout for `ENDTX` and let the transmitter rest, or
+    /// stop it if the transfer hasn't ended by then
+    fn end_tx(&mut self) -> Result<(), Error> {
+        let ended = Deadline::after(self.timeout).and_then(|deadline| {
+            deadline.wait(|| T::EVENTS_ENDTX::read().events_endtx().is_generated())
+        });
+        if ended.is_err() {
+            let stopped = self.stop_tx();
+            T::EVENTS_ENDTX::write(|w| w.events_endtx().notgenerated());
+            stopped?;
+            return ended;
+        }
+        T::EVENTS_ENDTX::write(|w| w.events_endtx().notgenerated());
+        T::TASKS_STOPTX::write(|w| w.tasks_stoptx().trigger());
+        Ok(())
+    }
+    /// Stop the transmitter, waiting up to the timeout for it to report
+    /// `TXSTOPPED`
+    fn stop_tx(&mut self) -> Result<(), Error> {
+        T::TASKS_STOPTX::write(|w| w.tasks_stoptx().trigger());
+        let stopped = Deadline::after(self.timeout)?.wait(|| {
+            T::EVENTS_TXSTOPPED::read()
+                .events_txstopped()
+                .is_generated()
+        });
+        T::EVENTS_TXSTOPPED::write(|w| w.events_txstopped().notgenerated());
+        self.tx_started &= stopped.is_err();
+        stopped
+    }
+    fn start_rx(&mut self, ptr: *mut u8, len: usize) {
+        T::EVENTS_ERROR::write(|w| w.events_error().notgenerated());
+        self.rx_started = true;
+        T::RXD_PTR::write(|w| w.ptr().bits(ptr as u32));
+        T::RXD_MAXCNT::write(|w| w.maxcnt().bits(len as u32));
+        T::TASKS_STARTRX::write(|w| w.tasks_startrx().trigger());
+    }
+    /// Stop the receiver, waiting up to the timeout for it to report `RXTO`
+    fn stop_rx(&mut self) -> Result<(), Error> {
+        T::TASKS_STOPRX::write(|w| w.tasks_stoprx().trigger());
+        let stopped = Deadline::after(self.timeout)?
+            .wait(|| T::EVENTS_RXTO::read().events_rxto().is_generated());
+        T::EVENTS_RXTO::write(|w| w.events_rxto().notgenerated());
+        self.rx_started &= stopped.is_err();
+        stopped
+    }
+    /// The line error of the last transfer, clearing it
+    fn take_error() -> Result<(), Error> {
+        let source = T::ERRORSRC::read();
+        if source.bits() == 0 {
+            return Ok(());
+        }
+        // Write one to clear
+        T::ERRORSRC::write(|w| w.bits(source.bits()));
+        T::EVENTS_ERROR::write(|w| w.events_error().notgenerated());
+        let kind = if source.overrun().is_present() {
+            ErrorKind::Overrun
+        } else if source.parity().is_present() {
+            ErrorKind::Parity
+        } else {
+            ErrorKind::Framing
+        };
+        Err(kind.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::gpio::Floating;
+    use crate::mem::sim;
+    use crate::rtc::{Monotonic, RTC2};
+    use crate::{Peripherals, P006, P108};
+
+    fn serial() -> Uarte<UARTE0, P006, P108, Floating> {
+        sim::reset();
+        let board = Board::new(unsafe { Peripherals::steal() });
+        Uarte::new(
+            board.uarte0,
+            board.uart.tx,
+            board.uart.rx,
+            Config::default(),
+        )
+    }
+
+    #[test]
+    fn pins_and_line_settings_are_configured() {
+        let _serial = serial();
+        // P0.06 and P1.08, connected
+        assert_eq!(sim::peek(PSEL_TXD0::ADDR), 6);
+        assert_eq!(sim::peek(PSEL_RXD0::ADDR), 1 << 5 | 8);
+        assert_eq!(sim::peek(PSEL_CTS0::ADDR), 1 << 31);
+        assert_eq!(sim::peek(BAUDRATE0::ADDR), 0x01D6_0000);
+        assert_eq!(sim::peek(CONFIG0::ADDR), 0);
+        assert_eq!(sim::peek(ENABLE0::ADDR), 8);
+        assert_eq!(<UARTE1 as Instance>::BAUDRATE::ADDR, 0x4002_8524);
+    }
+
+    #[test]
+    fn config_keeps_flow_control() {
+        let mut serial = serial();
+        let pins = unsafe { Peripherals::steal() }.p0.split();
+        let cts = pins.p0_02.into_pull_up_input();
+        let rts = pins.p0_03.into_push_pull_output(crate::Level::High);
+        serial.enable_flow_control(&cts, &rts);
+        serial.set_config(Config {
+            baudrate: Baudrate::Baud9600,
+            parity: Parity::Odd,
+            stop_bits: StopBits::Two,
+        });
+        assert_eq!(sim::peek(PSEL_CTS0::ADDR), 2);
+        assert_eq!(sim::peek(CONFIG0::ADDR), 1 | 0b111 << 1 | 1 << 4 | 1 << 8);
+        serial.disable_flow_control();
+        assert_eq!(sim::peek(CONFIG0::ADDR) & 1, 0);
+        assert_eq!(sim::peek(PSEL_RTS0::ADDR), 1 << 31);
+    }
+
+    #[test]
+    fn write_sends_through_easydma() {
+        let mut serial = serial();
+        sim::poke(EVENTS_ENDTX0::ADDR, 1);
+        serial.write(b"hi").unwrap();
+        assert_eq!(sim::peek(TXD_MAXCNT0::ADDR), 2);
+        assert_eq!(sim::peek(TASKS_STARTTX0::ADDR), 1);
+        assert_eq!(sim::peek(EVENTS_ENDTX0::ADDR), 0);
+    }
+
+    #[test]
+    fn line_errors_are_reported() {
+        let mut serial = serial();
+        let mut buffer = [0; 4];
+        sim::poke(EVENTS_ENDRX0::ADDR, 1);
+        sim::poke(ERRORSRC0::ADDR, 1);
+        let error = serial.read(&mut buffer).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Overrun);
+        assert_eq!(sim::peek(RXD_MAXCNT0::ADDR), 4);
+        assert_eq!(sim::peek(RXD_PTR0::ADDR), buffer.as_ptr() as u32);
+    }
+
+    #[test]
+    fn free_waits_for_both_directions_to_stop() {
+        let mut serial = serial();
+        static BYTES: [u8; 2] = *b"hi";
+        serial.start_write(&BYTES);
+        serial.start_read(Box::leak(Box::new([0; 4])));
+        assert_eq!(sim::peek(EVENTS_TXSTOPPED0::ADDR), 0);
+        sim::poke(EVENTS_TXSTOPPED0::ADDR, 1);
+        sim::poke(EVENTS_RXTO0::ADDR, 1);
+        let (_, pins) = serial.free();
+        assert!(pins.tx.is_set_high());
+        assert_eq!(sim::peek(TASKS_STOPTX0::ADDR), 1);
+        assert_eq!(sim::peek(TASKS_STOPRX0::ADDR), 1);
+        assert_eq!(sim::peek(EVENTS_TXSTOPPED0::ADDR), 0);
+        assert_eq!(sim::peek(EVENTS_RXTO0::ADDR), 0);
+        assert_eq!(sim::peek(ENABLE0::ADDR), 0);
+    }
+
+    #[test]
+    fn free_only_waits_for_the_directions_that_started() {
+        let mut tx_only = serial();
+        sim::poke(EVENTS_ENDTX0::ADDR, 1);
+        tx_only.write(b"hi").unwrap();
+        sim::poke(EVENTS_TXSTOPPED0::ADDR, 1);
+        // The receiver never started, so no RXTO is coming
+        tx_only.free();
+        assert_eq!(sim::peek(TASKS_STOPRX0::ADDR), 0);
+        assert_eq!(sim::peek(ENABLE0::ADDR), 0);
+        serial().free();
+        assert_eq!(sim::peek(TASKS_STOPTX0::ADDR), 0);
+    }
+
+    #[test]
+    fn free_gives_up_on_a_stop_after_the_timeout() {
+        let mut serial = serial();
+        let _clock = Monotonic::new(RTC2);
+        sim::poke(EVENTS_ENDTX0::ADDR, 1);
+        serial.write(b"hi").unwrap();
+        serial.set_timeout(Some(Duration::ZERO));
+        // Time stands still in the simulation, so only an empty wait expires
+        serial.free();
+        assert_eq!(sim::peek(ENABLE0::ADDR), 0);
+    }
+
+    #[test]
+    fn blocking_transfers_time_out() {
+        let mut serial = serial();
+        let _clock = Monotonic::new(RTC2);
+        serial.set_timeout(Some(Duration::ZERO));
+        // Time stands still in the simulation, so only an empty wait expires
+        let error = serial.write(b"hi").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert_eq!(sim::peek(TASKS_STOPTX0::ADDR), 1);
+        let error = serial.read(&mut [0; 4]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert_eq!(sim::peek(TASKS_STOPRX0::ADDR), 1);
+    }
+
+    #[test]
+    fn background_transfers_time_out() {
+        let mut serial = serial();
+        let _clock = Monotonic::new(RTC2);
+        serial.set_timeout(Some(Duration::ZERO));
+        static BYTES: [u8; 2] = *b"hi";
+        serial.start_write(&BYTES);
+        let (_, result) = serial.finish_write().unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Timeout);
+        assert_eq!(sim::peek(TASKS_STOPTX0::ADDR), 1);
+        serial.start_read(Box::leak(Box::new([0; 4])));
+        let (_, result) = serial.finish_read().unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Timeout);
+        assert_eq!(sim::peek(TASKS_STOPRX0::ADDR), 1);
+    }
+
+    #[test]
+    fn read_within_times_out_by_the_monotonic_clock() {
+        let mut serial = serial();
+        let _clock = Monotonic::new(RTC2);
+        let mut buffer = [0; 4];
+        sim::poke(EVENTS_RXTO0::ADDR, 1);
+        sim::poke(RXD_AMOUNT0::ADDR, 2);
+        // Time stands still in the simulation, so only an empty wait expires
+        assert_eq!(serial.read_within(&mut buffer, Duration::ZERO), Ok(2));
+        assert_eq!(sim::peek(TASKS_STOPRX0::ADDR), 1);
+        sim::poke(EVENTS_RXTO0::ADDR, 1);
+        sim::poke(RXD_AMOUNT0::ADDR, 0);
+        let error = serial.read_within(&mut buffer, Duration::ZERO).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+    }
+}